@@ -49,6 +49,19 @@ mod auth {
         action_id: ActionId,
         actions: StorageHashMap<(String, String),Action>,
         actions_auths: StorageHashMap<(AccountId, ActionId), Action>,
+        paused: bool,
+    }
+
+    #[ink(event)]
+    pub struct Paused {
+        #[ink(topic)]
+        guardian: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct Unpaused {
+        #[ink(topic)]
+        caller: AccountId,
     }
 
     impl Auth {
@@ -60,6 +73,7 @@ mod auth {
                 action_id: 0,
                 actions: StorageHashMap::new(),
                 actions_auths: StorageHashMap::new(),
+                paused: false,
             }
         }
         
@@ -77,10 +91,12 @@ mod auth {
            return false;
         }
 
+        /// `dao.unpause` is granted once by the owner, after that only its holders (the vote
+        /// manager) can grant or revoke it, so nobody can lift a pause without a vote.
         #[ink(message)]
         pub fn grant_permission(& mut self, account_id: AccountId, contract_name: String, function_name: String) ->  bool {
             let caller = self.env().caller();
-            assert!(self.owner == caller || self._has_permission(caller, String::from("auth"),String::from("grant")));
+            self.ensure_can_grant(caller, &contract_name, &function_name);
             if let Some(action) = self.actions.get(&(contract_name, function_name)){
                 let a: Action = Action{
                     action_id: action.action_id,
@@ -108,7 +124,7 @@ mod auth {
         #[ink(message)]
         pub fn revoke_permission(& mut self,account_id: AccountId,contract_name: String, function_name: String) -> bool {
             let caller = self.env().caller();
-            assert!(self.owner == caller || self._has_permission(caller, String::from("auth"),String::from("grant")));
+            self.ensure_can_grant(caller, &contract_name, &function_name);
             if let Some(action) = self.actions.get(&(contract_name, function_name)){
                 self.actions_auths.take(&(account_id, action.action_id));
                 return true;
@@ -121,6 +137,9 @@ mod auth {
         pub fn register_action(& mut self,contract_name: String, function_name: String, action_title: String) -> bool {
             let caller = self.env().caller();
             assert!(self.owner == caller || self._has_permission(caller, String::from("auth"),String::from("register")));
+            // re-registering would drop the holders of `dao.unpause`
+            assert!(!Self::is_unpause(&contract_name, &function_name)
+                || !self.actions.contains_key(&(contract_name.clone(), function_name.clone())));
             let action_id = self.action_id;
             self.action_id += 1;
             let action = Action{
@@ -138,6 +157,7 @@ mod auth {
         pub fn cancel_action(& mut self,contract_name: String, function_name: String) -> bool {
             let caller = self.env().caller();
             assert!(self.owner == caller || self._has_permission(caller, String::from("auth"),String::from("register")));
+            assert!(!Self::is_unpause(&contract_name, &function_name));
             self.actions.take(&(contract_name, function_name));
             true
         }
@@ -178,10 +198,73 @@ mod auth {
             actions_vec
         }
 
+        /// Accounts currently granted the action.
+        #[ink(message)]
+        pub fn get_action_holders(& self, contract_name: String, function_name: String) -> Vec<AccountId> {
+            let mut holders: Vec<AccountId> = Vec::new();
+            if let Some(action) = self.actions.get(&(contract_name, function_name)) {
+                for (account_id, action_id) in self.actions_auths.keys() {
+                    if *action_id == action.action_id {
+                        holders.push(*account_id);
+                    }
+                }
+            }
+            holders
+        }
+
+        fn is_unpause(contract_name: &str, function_name: &str) -> bool {
+            contract_name == "dao" && function_name == "unpause"
+        }
+
+        fn ensure_can_grant(&self, caller: AccountId, contract_name: &str, function_name: &str) {
+            let can_grant = self.owner == caller || self._has_permission(caller, String::from("auth"),String::from("grant"));
+            if !Self::is_unpause(contract_name, function_name) {
+                assert!(can_grant);
+                return;
+            }
+            let holders = self.get_action_holders(String::from(contract_name), String::from(function_name));
+            if holders.is_empty() {
+                assert!(can_grant);
+            } else {
+                assert!(holders.contains(&caller));
+            }
+        }
+
         #[ink(message)]
         pub fn get_auth_owner(& self) -> AccountId {
             return self.owner;
         }
+
+        /// DAO-wide emergency switch, checked by the vault, vote manager and erc20.
+        /// Only holders of the `dao.pause` guardian action can pause.
+        #[ink(message)]
+        pub fn pause(& mut self) -> bool {
+            let caller = self.env().caller();
+            assert!(self._has_permission(caller, String::from("dao"), String::from("pause")));
+            self.paused = true;
+            self.env().emit_event(Paused {
+                guardian: caller,
+            });
+            true
+        }
+
+        /// Only holders of `dao.unpause` can unpause, the dao manager grants it to the vote manager alone
+        /// and the owner can't grant it afterwards.
+        #[ink(message)]
+        pub fn unpause(& mut self) -> bool {
+            let caller = self.env().caller();
+            assert!(self._has_permission(caller, String::from("dao"), String::from("unpause")));
+            self.paused = false;
+            self.env().emit_event(Unpaused {
+                caller,
+            });
+            true
+        }
+
+        #[ink(message)]
+        pub fn is_paused(& self) -> bool {
+            self.paused
+        }
     }
 
     #[cfg(test)]
@@ -269,6 +352,41 @@ mod auth {
             }
         }
 
+        #[ink::test]
+        fn test_pause() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut auth = Auth::new(accounts.alice);
+            auth.register_action("dao".to_string(), "pause".to_string(), "Emergency Pause".to_string());
+            auth.register_action("dao".to_string(), "unpause".to_string(), "Unpause".to_string());
+            auth.grant_permission(accounts.alice, "dao".to_string(), "pause".to_string());
+            assert_eq!(auth.is_paused(), false);
+            auth.pause();
+            assert_eq!(auth.is_paused(), true);
+            auth.grant_permission(accounts.alice, "dao".to_string(), "unpause".to_string());
+            auth.unpause();
+            assert_eq!(auth.is_paused(), false);
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn test_pause_without_permission() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut auth = Auth::new(accounts.alice);
+            auth.register_action("dao".to_string(), "pause".to_string(), "Emergency Pause".to_string());
+            auth.pause();
+        }
+
+        #[ink::test]
+        #[should_panic]
+        fn test_owner_cannot_grant_unpause_again() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut auth = Auth::new(accounts.alice);
+            auth.register_action("dao".to_string(), "unpause".to_string(), "Unpause".to_string());
+            // bob stands for the vote manager
+            assert!(auth.grant_permission(accounts.bob, "dao".to_string(), "unpause".to_string()));
+            auth.grant_permission(accounts.alice, "dao".to_string(), "unpause".to_string());
+        }
+
         #[ink::test]
         fn test_show_actions_by_contract() {
             let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
@@ -93,8 +93,12 @@ mod dao_manager {
         owner: AccountId,
         name: String,
         symbol: String,
-        total_supply: u64,
+        total_supply: Balance,
         decimals: u8,
+        // optional cap on the token's total supply
+        max_supply: Option<Balance>,
+        // non-transferable token, voting power can't be bought
+        soulbound: bool,
     }
 
     #[derive(
@@ -118,6 +122,8 @@ mod dao_manager {
     )]
     pub struct AuthParam {
         owner: AccountId,
+        // accounts allowed to trigger the emergency pause
+        guardians: Vec<AccountId>,
     }
 
     /// DAO component instance addresses
@@ -235,6 +241,7 @@ mod dao_manager {
             let vault_addr = self.component_addrs.vault_addr.unwrap();
             let mut vault_instance: VaultManager = ink_env::call::FromAccountId::from_account_id(vault_addr);
             vault_instance.add_vault_token(erc20_addr);
+            vault_instance.set_dao_token(erc20_addr);
         }
 
         fn _after_init_auth(&mut self, auth_code_hash: Option<&Hash>, auth: AuthParam) {
@@ -253,6 +260,15 @@ mod dao_manager {
             self.component_addrs
         }
 
+        /// Whether the DAO-wide emergency pause is active.
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            match &self.components.auth {
+                None => false,
+                Some(auth) => auth.is_paused(),
+            }
+        }
+
         /// init base
         fn _init_base(&mut self, base_code_hash: Option<&Hash>,
                       param: BaseParam, salt: &Vec<u8>) -> bool {
@@ -291,7 +307,7 @@ mod dao_manager {
             assert!(total_balance > CONTRACT_INIT_BALANCE, "not enough unit to instance contract");
             // let vault_addr = self.component_addrs.vault_addr.unwrap();
             let erc20_instance_params = Erc20::new(param.name, param.symbol,
-                0, param.decimals, Self::env().account_id())
+                0, param.decimals, param.max_supply, param.soulbound, Self::env().account_id())
                 .endowment(CONTRACT_INIT_BALANCE)
                 .code_hash(erc20_code_hash)
                 .salt_bytes(salt)
@@ -300,8 +316,15 @@ mod dao_manager {
             let erc20_addr = erc20_init_result.expect("failed at instantiating the `Erc20` contract");
             let mut erc20_instance: Erc20 = ink_env::call::FromAccountId::from_account_id(erc20_addr);
 
-            erc20_instance.mint_token_by_owner(param.owner, param.total_supply);
-            erc20_instance.transfer_owner(param.owner);
+            if let Some(auth_addr) = self.component_addrs.auth_addr {
+                erc20_instance.set_auth_contract(auth_addr).expect("failed to link auth to erc20");
+            }
+            erc20_instance.mint_token_by_owner(param.owner, param.total_supply).expect("failed to mint erc20");
+            // proposals can mint through the vote manager
+            if let Some(vote_addr) = self.component_addrs.vote_addr {
+                erc20_instance.add_minter(vote_addr).expect("failed to add vote as minter");
+            }
+            erc20_instance.transfer_owner(param.owner).expect("failed to transfer erc20 owner");
 
             self.components.erc20 = Some(erc20_instance);
             self.component_addrs.erc20_addr = Some(erc20_addr);
@@ -376,13 +399,24 @@ mod dao_manager {
 
             // register inner action
             auth_instance.register_action(String::from("vault"), String::from("add_vault_token"), String::from("vault.add_vault_token"));
+            auth_instance.register_action(String::from("vault"), String::from("remove_vault_token"), String::from("vault.remove_vault_token"));
             auth_instance.register_action(String::from("vote"), String::from("new"), String::from("Create Voting"));
             auth_instance.register_action(String::from("vote"), String::from("vote"), String::from("Vote"));
             auth_instance.register_action(String::from("auth"), String::from("grant"), String::from("Grant/Revoke Permission"));
             auth_instance.register_action(String::from("auth"), String::from("register"), String::from("Register/Cancel Action"));
+            auth_instance.register_action(String::from("dao"), String::from("pause"), String::from("Emergency Pause"));
+            auth_instance.register_action(String::from("dao"), String::from("unpause"), String::from("Unpause"));
+            auth_instance.register_action(String::from("erc20"), String::from("burn"), String::from("Burn Tokens"));
+            auth_instance.register_action(String::from("erc20"), String::from("minter"), String::from("Manage Minters"));
+            auth_instance.register_action(String::from("erc20"), String::from("pause"), String::from("Pause Token"));
+            auth_instance.register_action(String::from("erc20"), String::from("freeze"), String::from("Freeze Accounts"));
 
             // grant inner action
             auth_instance.grant_permission(dao_addr, String::from("vault"), String::from("add_vault_token"));
+            auth_instance.grant_permission(dao_addr, String::from("vault"), String::from("remove_vault_token"));
+            for guardian in &auth.guardians {
+                auth_instance.grant_permission(*guardian, String::from("dao"), String::from("pause"));
+            }
 
             self.components.auth = Some(auth_instance);
             self.component_addrs.auth_addr = Some(auth_addr);
@@ -412,6 +446,9 @@ mod dao_manager {
             let vault_instance: VaultManager = ink_env::call::FromAccountId::from_account_id(vault_addr);
             self.components.vault = Some(vault_instance);
             self.component_addrs.vault_addr = Some(vault_addr);
+            // membership dues are paid into the vault
+            let mut org_instance: OrgManager = ink_env::call::FromAccountId::from_account_id(org_addr);
+            org_instance.set_vault_contract(vault_addr);
             true
         }
 
@@ -426,8 +463,9 @@ mod dao_manager {
             // instance org
             let vault_addr = self.component_addrs.vault_addr.unwrap();
             let auth_addr = self.component_addrs.auth_addr.unwrap();
+            let org_addr = self.component_addrs.org_addr.unwrap();
             // let salt = version.to_le_bytes();
-            let vote_instance_params = VoteManager::new(vault_addr, auth_addr)
+            let vote_instance_params = VoteManager::new(vault_addr, auth_addr, org_addr)
                 .endowment(CONTRACT_INIT_BALANCE)
                 .code_hash(vote_code_hash)
                 .salt_bytes(salt)
@@ -438,11 +476,32 @@ mod dao_manager {
             self.components.vote = Some(vote_instance);
             self.component_addrs.vote_addr = Some(vote_addr);
 
+            let mut vault_instance: VaultManager = ink_env::call::FromAccountId::from_account_id(vault_addr);
+            vault_instance.set_vote_contract(vote_addr);
+
             let mut auth_instance: Auth = ink_env::call::FromAccountId::from_account_id(auth_addr);
             // register inner action
             auth_instance.register_action(String::from("vault"), String::from("withdraw"), String::from("vault.withdraw"));
+            auth_instance.register_action(String::from("vault"), String::from("budget"), String::from("vault.budget"));
+            auth_instance.register_action(String::from("vault"), String::from("policy"), String::from("vault.policy"));
             // grant inner action
             auth_instance.grant_permission(vote_addr, String::from("vault"), String::from("withdraw"));
+            auth_instance.grant_permission(vote_addr, String::from("vault"), String::from("budget"));
+            auth_instance.grant_permission(vote_addr, String::from("vault"), String::from("policy"));
+            // only a passed vote can lift an emergency pause
+            auth_instance.grant_permission(vote_addr, String::from("dao"), String::from("unpause"));
+            auth_instance.register_action(String::from("org"), String::from("remove_member"), String::from("Remove Member"));
+            auth_instance.grant_permission(vote_addr, String::from("org"), String::from("remove_member"));
+            // minters of the DAO token are managed by vote
+            auth_instance.grant_permission(vote_addr, String::from("erc20"), String::from("minter"));
+            // ragequit burns the shares, soulbound tokens can't be approved to the vault
+            auth_instance.grant_permission(vault_addr, String::from("erc20"), String::from("burn"));
+            auth_instance.register_action(String::from("org"), String::from("admit_member"), String::from("Admit Member"));
+            auth_instance.register_action(String::from("org"), String::from("role"), String::from("Create Role"));
+            auth_instance.grant_permission(vote_addr, String::from("org"), String::from("role"));
+            auth_instance.grant_permission(vote_addr, String::from("org"), String::from("admit_member"));
+            let mut org_instance: OrgManager = ink_env::call::FromAccountId::from_account_id(org_addr);
+            org_instance.set_vote_contract(vote_addr);
 
             true
         }
@@ -3,25 +3,66 @@
 extern crate alloc;
 use ink_lang as ink;
 pub use self::erc20::Erc20;
+pub use self::erc20::PSP22Error;
+pub use self::erc20::ON_TOKEN_RECEIVED_SELECTOR;
 
 #[ink::contract]
 mod erc20 {
     use alloc::string::String;
+    use ink_prelude::vec::Vec;
     use ink_storage::{
         collections::HashMap as StorageHashMap,
         traits::{PackedLayout, SpreadLayout},
     };
+    use auth::Auth;
+    use ink_env::hash::Blake2x256;
+    use ink_env::call::{build_call, utils::ReturnType, ExecutionInput, Selector};
+
+    /// Errors of the PSP22 standard.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum PSP22Error {
+        /// Custom error type for cases that the standard does not cover.
+        Custom(String),
+        /// Returned if not enough balance to fulfill a request is available.
+        InsufficientBalance,
+        /// Returned if not enough allowance to fulfill a request is available.
+        InsufficientAllowance,
+        /// Returned if recipient's address is zero.
+        ZeroRecipientAddress,
+        /// Returned if sender's address is zero.
+        ZeroSenderAddress,
+        /// Returned if a safe transfer check fails.
+        SafeTransferCheckFailed(String),
+    }
+
+    pub type Result<T> = core::result::Result<T, PSP22Error>;
+
+    /// Selector of the `on_token_received(operator: AccountId, from: AccountId, value: Balance, data: Vec<u8>) -> Result<(), PSP22Error>`
+    /// message a contract implements to accept tokens sent with `transfer_and_call`.
+    pub const ON_TOKEN_RECEIVED_SELECTOR: [u8; 4] = [0x5f, 0xc1, 0x9b, 0x73];
 
     /// Indicates whether a transaction is already confirmed or needs further confirmations.
     #[ink(storage)]
     pub struct Erc20 {
         name: String,
         symbol: String,
-        total_supply: u64,
+        total_supply: Balance,
         decimals: u8,
         owner: AccountId,
-        balances: StorageHashMap<AccountId, u64>,
-        allowances: StorageHashMap<(AccountId, AccountId), u64>,
+        balances: StorageHashMap<AccountId, Balance>,
+        allowances: StorageHashMap<(AccountId, AccountId), Balance>,
+        auth_contract_address: Option<AccountId>,
+        // upper bound of total supply, none means uncapped
+        max_supply: Option<Balance>,
+        minters: StorageHashMap<AccountId, AccountId>,
+        // next permit nonce of every owner
+        nonces: StorageHashMap<AccountId, u64>,
+        // non-transferable token, balances only change by mint and burn
+        soulbound: bool,
+        // token-level pause, stops all transfers
+        paused: bool,
+        frozen: StorageHashMap<AccountId, AccountId>,
     }
 
     #[ink(event)]
@@ -30,8 +71,7 @@ mod erc20 {
         from: Option<AccountId>,
         #[ink(topic)]
         to: Option<AccountId>,
-        #[ink(topic)]
-        value: u64,
+        value: Balance,
     }
 
     #[ink(event)]
@@ -40,8 +80,77 @@ mod erc20 {
         owner: AccountId,
         #[ink(topic)]
         spender: AccountId,
+        value: Balance,
+    }
+
+    #[ink(event)]
+    pub struct MinterAdded {
+        #[ink(topic)]
+        minter: AccountId,
+        #[ink(topic)]
+        admin: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct MinterRemoved {
+        #[ink(topic)]
+        minter: AccountId,
+        #[ink(topic)]
+        admin: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct TokenPaused {
+        #[ink(topic)]
+        admin: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct TokenUnpaused {
+        #[ink(topic)]
+        admin: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct AccountFrozen {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        admin: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct AccountUnfrozen {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        admin: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct BatchTransfer {
         #[ink(topic)]
-        value: u64,
+        from: AccountId,
+        count: u32,
+        total: Balance,
+    }
+
+    #[ink(event)]
+    pub struct BatchMint {
+        #[ink(topic)]
+        minter: AccountId,
+        count: u32,
+        total: Balance,
+    }
+
+    /// Tokens burned by the owner or an auth `erc20.burn` holder rather than their holder.
+    #[ink(event)]
+    pub struct AdminBurn {
+        #[ink(topic)]
+        admin: AccountId,
+        #[ink(topic)]
+        from: AccountId,
+        value: Balance,
     }
 
     #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
@@ -52,15 +161,17 @@ mod erc20 {
     pub struct DisplayInfo {
         name: String,
         symbol: String,
-        total_supply: u64,
+        total_supply: Balance,
         decimals: u8,
         owner: AccountId,
+        paused: bool,
+        frozen: Vec<AccountId>,
     }
 
     impl Erc20 {
         #[ink(constructor)]
-        pub fn new(name: String, symbol: String, initial_supply: u64, decimals: u8, controller: AccountId) -> Self {
-            let mut balances = StorageHashMap::new();
+        pub fn new(name: String, symbol: String, initial_supply: Balance, decimals: u8, max_supply: Option<Balance>, soulbound: bool, controller: AccountId) -> Self {
+            let balances = StorageHashMap::new();
             let mut instance = Self {
                 name: name,
                 symbol: symbol,
@@ -70,29 +181,52 @@ mod erc20 {
                 balances,
                 allowances: StorageHashMap::new(),
                 owner: controller,
+                auth_contract_address: None,
+                max_supply,
+                minters: StorageHashMap::new(),
+                nonces: StorageHashMap::new(),
+                soulbound,
+                paused: false,
+                frozen: StorageHashMap::new(),
             };
-            instance._mint_token(controller, initial_supply);
+            instance._mint_token(controller, initial_supply).expect("initial mint failed");
             instance
         }
 
-        #[ink(message)]
-        pub fn name(&self) -> String {
-            self.name.clone()
+        #[ink(message, selector = "0x3d261bd4")]
+        pub fn token_name(&self) -> Option<String> {
+            Some(self.name.clone())
+        }
+
+        #[ink(message, selector = "0x34205be5")]
+        pub fn token_symbol(&self) -> Option<String> {
+            Some(self.symbol.clone())
+        }
+
+        #[ink(message, selector = "0x7271b782")]
+        pub fn token_decimals(&self) -> u8 {
+            self.decimals
+        }
+
+        #[ink(message, selector = "0x162df8c2")]
+        pub fn total_supply(&self) -> Balance {
+            self.total_supply
         }
 
         #[ink(message)]
-        pub fn symbol(&self) -> String {
-            self.symbol.clone()
+        pub fn max_supply(&self) -> Option<Balance> {
+            self.max_supply
         }
 
+        /// How much can still be minted before hitting the cap, none if uncapped.
         #[ink(message)]
-        pub fn total_supply(&self) -> u64 {
-            self.total_supply
+        pub fn remaining_mintable(&self) -> Option<Balance> {
+            self.max_supply.map(|max_supply| max_supply.saturating_sub(self.total_supply))
         }
 
         #[ink(message)]
-        pub fn decimals(&self) -> u8 {
-            self.decimals
+        pub fn is_soulbound(&self) -> bool {
+            self.soulbound
         }
 
         #[ink(message)]
@@ -107,95 +241,437 @@ mod erc20 {
                 symbol: self.symbol.clone(),
                 total_supply: self.total_supply,
                 decimals: self.decimals,
-                owner: self.owner
+                owner: self.owner,
+                paused: self.paused,
+                frozen: self.frozen.keys().cloned().collect(),
             }
         }
 
-        #[ink(message)]
-        pub fn balance_of(&self, owner: AccountId) -> u64 {
+        #[ink(message, selector = "0x6568382f")]
+        pub fn balance_of(&self, owner: AccountId) -> Balance {
             self.balance_of_or_zero(&owner)
         }
 
-        #[ink(message)]
-        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> u64 {
+        #[ink(message, selector = "0x4d47d921")]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
             self.allowance_of_or_zero(&owner, &spender)
         }
 
-        #[ink(message)]
-        pub fn transfer(&mut self, to: AccountId, value: u64) -> bool {
+        /// `data` is passed through for receivers, it is not interpreted by the token.
+        #[ink(message, selector = "0xdb20f9f5")]
+        pub fn transfer(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<()> {
+            self.ensure_transferable()?;
             let from = self.env().caller();
-            self.transfer_from_to(from, to, value)
+            self.transfer_from_to(from, to, value, data)
         }
 
+        /// Transfers to a contract and notifies it through `on_token_received`. The whole
+        /// call reverts if the receiver rejects the tokens or does not implement the hook.
         #[ink(message)]
-        pub fn approve(&mut self, spender: AccountId, value: u64) -> bool {
-            let owner = self.env().caller();
-            self.allowances.insert((owner, spender), value);
-            self.env().emit_event(Approval {
-                owner,
-                spender,
-                value,
-            });
-            true
+        pub fn transfer_and_call(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<()> {
+            self.ensure_transferable()?;
+            let from = self.env().caller();
+            self.transfer_from_to(from, to, value, data.clone())?;
+            let hook_result = build_call::<ink_env::DefaultEnvironment>()
+                .callee(to)
+                .gas_limit(0)
+                .transferred_value(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ON_TOKEN_RECEIVED_SELECTOR))
+                        .push_arg(from)
+                        .push_arg(from)
+                        .push_arg(value)
+                        .push_arg(data)
+                )
+                .returns::<ReturnType<Result<()>>>()
+                .fire();
+            let error = match hook_result {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(PSP22Error::SafeTransferCheckFailed(reason))) => reason,
+                Ok(Err(_)) => String::from("receiver rejected the transfer"),
+                Err(_) => String::from("receiver does not accept tokens"),
+            };
+            // revert the transfer together with the error
+            ink_env::return_value::<Result<()>>(
+                ink_env::ReturnFlags::default().set_reverted(true),
+                &Err(PSP22Error::SafeTransferCheckFailed(error)),
+            )
         }
 
-        #[ink(message)]
+        #[ink(message, selector = "0x54b3c76e")]
         pub fn transfer_from(
             &mut self,
             from: AccountId,
             to: AccountId,
-            value: u64,
-        ) -> bool {
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            self.ensure_transferable()?;
             let caller = self.env().caller();
             let allowance = self.allowance_of_or_zero(&from, &caller);
             if allowance < value {
-                return false
+                return Err(PSP22Error::InsufficientAllowance)
+            }
+            self.transfer_from_to(from, to, value, data)?;
+            self._approve(from, caller, allowance - value)
+        }
+
+        #[ink(message, selector = "0xb20f1bbd")]
+        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            self._approve(owner, spender, value)
+        }
+
+        #[ink(message, selector = "0x96d6b57a")]
+        pub fn increase_allowance(&mut self, spender: AccountId, delta_value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            let allowance = self.allowance_of_or_zero(&owner, &spender);
+            let value = allowance.checked_add(delta_value)
+                .ok_or(PSP22Error::Custom(String::from("allowance overflow")))?;
+            self._approve(owner, spender, value)
+        }
+
+        #[ink(message, selector = "0xfecb57d5")]
+        pub fn decrease_allowance(&mut self, spender: AccountId, delta_value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            let allowance = self.allowance_of_or_zero(&owner, &spender);
+            if allowance < delta_value {
+                return Err(PSP22Error::InsufficientAllowance)
+            }
+            self._approve(owner, spender, allowance - delta_value)
+        }
+
+        /// Sets `spender`'s allowance over `owner`'s tokens from the owner's ecdsa
+        /// signature over `permit_hash`, so anyone can submit the approval.
+        #[ink(message)]
+        pub fn permit(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            deadline: u64,
+            signature: [u8; 65],
+        ) -> Result<()> {
+            if self.env().block_timestamp() > deadline {
+                return Err(PSP22Error::Custom(String::from("permit expired")))
+            }
+            let message_hash = self.permit_hash(owner, spender, value, deadline);
+            let mut public_key = [0u8; 33];
+            if ink_env::ecdsa_recover(&signature, &message_hash, &mut public_key).is_err() {
+                return Err(PSP22Error::Custom(String::from("invalid signature")))
             }
-            self.allowances.insert((from, caller), allowance - value);
-            self.transfer_from_to(from, to, value)
+            // substrate ecdsa accounts are the blake2 hash of the compressed public key
+            let mut signer = [0u8; 32];
+            ink_env::hash_bytes::<Blake2x256>(&public_key, &mut signer);
+            if AccountId::from(signer) != owner {
+                return Err(PSP22Error::Custom(String::from("invalid signature")))
+            }
+            self._approve(owner, spender, value)?;
+            let nonce = self.nonces(owner);
+            self.nonces.insert(owner, nonce + 1);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn nonces(&self, owner: AccountId) -> u64 {
+            *self.nonces.get(&owner).unwrap_or(&0)
+        }
+
+        /// The hash the owner signs for `permit`, bound to this token and the
+        /// owner's current nonce.
+        #[ink(message)]
+        pub fn permit_hash(&self, owner: AccountId, spender: AccountId, value: Balance, deadline: u64) -> [u8; 32] {
+            let payload = (self.env().account_id(), owner, spender, value, self.nonces(owner), deadline);
+            let mut message_hash = [0u8; 32];
+            ink_env::hash_encoded::<Blake2x256, _>(&payload, &mut message_hash);
+            message_hash
         }
 
         #[ink(message)]
         pub fn transfer_owner(
             &mut self,
             to: AccountId,
-        ) -> bool {
-            let caller = self.env().caller();
-            assert_eq!(caller == self.owner, true);
+        ) -> Result<()> {
+            self.ensure_owner()?;
             self.owner = to;
-            true
+            Ok(())
         }
 
+        /// Link the DAO's auth contract, transfers stop while the DAO is paused.
         #[ink(message)]
-        pub fn mint_token_by_owner(
+        pub fn set_auth_contract(
             &mut self,
-            to: AccountId,
-            value: u64,
-        ) -> bool {
+            auth_contract_address: AccountId,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            self.auth_contract_address = Some(auth_contract_address);
+            Ok(())
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(PSP22Error::Custom(String::from("caller is not the owner")))
+            }
+            Ok(())
+        }
+
+        fn is_dao_paused(&self) -> bool {
+            match self.auth_contract_address {
+                None => false,
+                Some(auth_contract_address) => {
+                    let auth: Auth = ink_env::call::FromAccountId::from_account_id(auth_contract_address);
+                    auth.is_paused()
+                }
+            }
+        }
+
+        /// Transfers to many recipients at once. Nothing is transferred unless every
+        /// transfer can be made.
+        #[ink(message)]
+        pub fn batch_transfer(&mut self, recipients: Vec<(AccountId, Balance)>) -> Result<()> {
+            self.ensure_transferable()?;
+            let from = self.env().caller();
+            let total = Self::batch_total(&recipients)?;
+            if self.balance_of_or_zero(&from) < total {
+                return Err(PSP22Error::InsufficientBalance)
+            }
+            // a returned error doesn't revert storage, check every transfer before the first one
+            for (to, _) in &recipients {
+                self.ensure_can_transfer(from, *to)?;
+            }
+            for (to, value) in &recipients {
+                self.transfer_from_to(from, *to, *value, Vec::new())?;
+            }
+            self.env().emit_event(BatchTransfer {
+                from,
+                count: recipients.len() as u32,
+                total,
+            });
+            Ok(())
+        }
+
+        /// Airdrop by owner, all or nothing like `batch_transfer`.
+        #[ink(message)]
+        pub fn batch_mint(&mut self, recipients: Vec<(AccountId, Balance)>) -> Result<()> {
+            self.ensure_owner()?;
+            let total = Self::batch_total(&recipients)?;
+            if let Some(remaining) = self.remaining_mintable() {
+                if total > remaining {
+                    return Err(PSP22Error::Custom(String::from("max supply exceeded")))
+                }
+            }
+            for (to, value) in &recipients {
+                self._mint_token(*to, *value)?;
+            }
+            self.env().emit_event(BatchMint {
+                minter: self.env().caller(),
+                count: recipients.len() as u32,
+                total,
+            });
+            Ok(())
+        }
+
+        /// Sum of a batch, fails on overflow or a zero recipient so the batch can be
+        /// rejected before any balance changes.
+        fn batch_total(recipients: &[(AccountId, Balance)]) -> Result<Balance> {
+            let mut total: Balance = 0;
+            for (to, value) in recipients {
+                if *to == AccountId::default() {
+                    return Err(PSP22Error::ZeroRecipientAddress)
+                }
+                total = total.checked_add(*value)
+                    .ok_or(PSP22Error::Custom(String::from("batch overflow")))?;
+            }
+            Ok(total)
+        }
+
+        /// Stops all transfers of this token, by owner or a holder of the auth `erc20.pause` permission.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_admin(caller, "pause")?;
+            self.paused = true;
+            self.env().emit_event(TokenPaused {
+                admin: caller,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_admin(caller, "pause")?;
+            self.paused = false;
+            self.env().emit_event(TokenUnpaused {
+                admin: caller,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Blocks transfers from and to `account`, by owner or a holder of the auth `erc20.freeze` permission.
+        #[ink(message)]
+        pub fn freeze(&mut self, account: AccountId) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_admin(caller, "freeze")?;
+            if self.frozen.insert(account, account).is_none() {
+                self.env().emit_event(AccountFrozen {
+                    account,
+                    admin: caller,
+                });
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unfreeze(&mut self, account: AccountId) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_admin(caller, "freeze")?;
+            if self.frozen.take(&account).is_some() {
+                self.env().emit_event(AccountUnfrozen {
+                    account,
+                    admin: caller,
+                });
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_frozen(&self, account: AccountId) -> bool {
+            self.frozen.contains_key(&account)
+        }
+
+        fn ensure_admin(&self, caller: AccountId, function_name: &str) -> Result<()> {
+            if caller != self.owner && !self.has_auth_permission(caller, function_name) {
+                return Err(PSP22Error::Custom(String::from("caller is not allowed")))
+            }
+            Ok(())
+        }
+
+        /// Mints by a minter, the owner can always mint.
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
             let caller = self.env().caller();
-            assert_eq!(caller == self.owner, true);
+            if caller != self.owner && !self.minters.contains_key(&caller) {
+                return Err(PSP22Error::Custom(String::from("caller is not a minter")))
+            }
             self._mint_token(to, value)
         }
 
+        /// Adds a minter, by owner or a holder of the auth `erc20.minter` permission.
+        #[ink(message)]
+        pub fn add_minter(&mut self, minter: AccountId) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_minter_admin(caller)?;
+            if self.minters.insert(minter, minter).is_none() {
+                self.env().emit_event(MinterAdded {
+                    minter,
+                    admin: caller,
+                });
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_minter(&mut self, minter: AccountId) -> Result<()> {
+            let caller = self.env().caller();
+            self.ensure_minter_admin(caller)?;
+            if self.minters.take(&minter).is_some() {
+                self.env().emit_event(MinterRemoved {
+                    minter,
+                    admin: caller,
+                });
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_minter(&self, account: AccountId) -> bool {
+            self.minters.contains_key(&account)
+        }
+
+        #[ink(message)]
+        pub fn get_minters(&self) -> Vec<AccountId> {
+            self.minters.keys().cloned().collect()
+        }
+
+        fn ensure_minter_admin(&self, caller: AccountId) -> Result<()> {
+            if caller != self.owner && !self.has_auth_permission(caller, "minter") {
+                return Err(PSP22Error::Custom(String::from("caller can not manage minters")))
+            }
+            Ok(())
+        }
+
         #[ink(message)]
-        pub fn destroy_token_by_owner(
+        pub fn mint_token_by_owner(
             &mut self,
-            from: AccountId,
-            value: u64,
-        ) -> bool {
-            assert_eq!(value > 0, true);
-            self._destroy_token(from, value)
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            self._mint_token(to, value)
+        }
+
+        /// Burns `value` of the caller's own tokens.
+        #[ink(message)]
+        pub fn burn(&mut self, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            self._destroy_token(caller, value)
+        }
+
+        /// Burns `value` of `from`'s tokens out of the caller's allowance.
+        #[ink(message)]
+        pub fn burn_from(&mut self, from: AccountId, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            let allowance = self.allowance_of_or_zero(&from, &caller);
+            let remaining = allowance.checked_sub(value)
+                .ok_or(PSP22Error::InsufficientAllowance)?;
+            self._destroy_token(from, value)?;
+            self._approve(from, caller, remaining)
+        }
+
+        /// Burns `value` of any account's tokens, for the owner or holders of the
+        /// auth `erc20.burn` permission.
+        #[ink(message)]
+        pub fn admin_burn(&mut self, from: AccountId, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            if caller != self.owner && !self.has_auth_permission(caller, "burn") {
+                return Err(PSP22Error::Custom(String::from("caller can not burn")))
+            }
+            self._destroy_token(from, value)?;
+            self.env().emit_event(AdminBurn {
+                admin: caller,
+                from,
+                value,
+            });
+            Ok(())
+        }
+
+        fn has_auth_permission(&self, account: AccountId, function_name: &str) -> bool {
+            match self.auth_contract_address {
+                None => false,
+                Some(auth_contract_address) => {
+                    let auth: Auth = ink_env::call::FromAccountId::from_account_id(auth_contract_address);
+                    auth.has_permission(account, String::from("erc20"), String::from(function_name))
+                }
+            }
         }
 
         fn transfer_from_to(
             &mut self,
             from: AccountId,
             to: AccountId,
-            value: u64,
-        ) -> bool {
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<()> {
+            self.ensure_can_transfer(from, to)?;
             let from_balance = self.balance_of_or_zero(&from);
             if from_balance < value {
-                return false
+                return Err(PSP22Error::InsufficientBalance)
             }
             self.balances.insert(from, from_balance - value);
             let to_balance = self.balance_of_or_zero(&to);
@@ -205,10 +681,54 @@ mod erc20 {
                 to: Some(to),
                 value,
             });
-            true
+            Ok(())
+        }
+
+        /// Checks everything but the balance of a transfer from `from` to `to`.
+        fn ensure_can_transfer(&self, from: AccountId, to: AccountId) -> Result<()> {
+            if from == AccountId::default() {
+                return Err(PSP22Error::ZeroSenderAddress)
+            }
+            if to == AccountId::default() {
+                return Err(PSP22Error::ZeroRecipientAddress)
+            }
+            if self.is_dao_paused() {
+                return Err(PSP22Error::Custom(String::from("dao is paused")))
+            }
+            if self.paused {
+                return Err(PSP22Error::Custom(String::from("token is paused")))
+            }
+            if self.frozen.contains_key(&from) || self.frozen.contains_key(&to) {
+                return Err(PSP22Error::Custom(String::from("account is frozen")))
+            }
+            Ok(())
+        }
+
+        fn ensure_transferable(&self) -> Result<()> {
+            if self.soulbound {
+                return Err(PSP22Error::Custom(String::from("token is soulbound")))
+            }
+            Ok(())
+        }
+
+        fn _approve(&mut self, owner: AccountId, spender: AccountId, value: Balance) -> Result<()> {
+            self.ensure_transferable()?;
+            if owner == AccountId::default() {
+                return Err(PSP22Error::ZeroSenderAddress)
+            }
+            if spender == AccountId::default() {
+                return Err(PSP22Error::ZeroRecipientAddress)
+            }
+            self.allowances.insert((owner, spender), value);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
+            Ok(())
         }
 
-        fn balance_of_or_zero(&self, owner: &AccountId) -> u64 {
+        fn balance_of_or_zero(&self, owner: &AccountId) -> Balance {
             *self.balances.get(owner).unwrap_or(&0)
         }
 
@@ -216,46 +736,56 @@ mod erc20 {
             &self,
             owner: &AccountId,
             spender: &AccountId,
-        ) -> u64 {
+        ) -> Balance {
             *self.allowances.get(&(*owner, *spender)).unwrap_or(&0)
         }
 
         fn _mint_token(
             &mut self,
             to: AccountId,
-            amount: u64,
-        ) -> bool {
-            let total_supply = self.total_supply();
-            assert_eq!(total_supply + amount >= total_supply, true);
-            let to_balance = self.balance_of_or_zero(&to);
-            assert_eq!(to_balance + amount >= to_balance, true);
-            self.total_supply += amount;
-            self.balances.insert(to, to_balance + amount);
+            amount: Balance,
+        ) -> Result<()> {
+            if to == AccountId::default() {
+                return Err(PSP22Error::ZeroRecipientAddress)
+            }
+            let total_supply = self.total_supply.checked_add(amount)
+                .ok_or(PSP22Error::Custom(String::from("supply overflow")))?;
+            if let Some(max_supply) = self.max_supply {
+                if total_supply > max_supply {
+                    return Err(PSP22Error::Custom(String::from("max supply exceeded")))
+                }
+            }
+            let to_balance = self.balance_of_or_zero(&to) + amount;
+            self.total_supply = total_supply;
+            self.balances.insert(to, to_balance);
             self.env().emit_event(Transfer {
                 from: None,
                 to: Some(to),
                 value: amount,
             });
-            true
+            Ok(())
         }
 
         fn _destroy_token(
             &mut self,
             from: AccountId,
-            amount: u64,
-        ) -> bool {
-            let total_supply = self.total_supply();
-            assert_eq!(total_supply - amount <= total_supply, true);
-            let from_balance = self.balance_of_or_zero(&from);
-            assert_eq!(from_balance - amount <= from_balance, true);
-            self.total_supply -= amount;
-            self.balances.insert(from, from_balance - amount);
+            amount: Balance,
+        ) -> Result<()> {
+            if amount == 0 {
+                return Err(PSP22Error::Custom(String::from("zero amount")))
+            }
+            let from_balance = self.balance_of_or_zero(&from).checked_sub(amount)
+                .ok_or(PSP22Error::InsufficientBalance)?;
+            let total_supply = self.total_supply.checked_sub(amount)
+                .ok_or(PSP22Error::Custom(String::from("supply underflow")))?;
+            self.total_supply = total_supply;
+            self.balances.insert(from, from_balance);
             self.env().emit_event(Transfer {
                 from: Some(from),
                 to: None,
                 value: amount,
             });
-            true
+            Ok(())
         }
     }
 
@@ -273,18 +803,20 @@ mod erc20 {
                 , "SDT".to_string()
                 , 100000000
                 , 4
+                , None
+                , false
                 , accounts.alice
                 );
 
-            ink_env::debug_println!("{}", &token.name());
-            ink_env::debug_println!("{}",&token.symbol());
+            ink_env::debug_println!("{}", &token.token_name().unwrap());
+            ink_env::debug_println!("{}",&token.token_symbol().unwrap());
             ink_env::debug_println!("{}",&format!("total supply  is {}", token.total_supply()));
-            ink_env::debug_println!("{}",&format!("decimals  is {}", token.decimals()));
+            ink_env::debug_println!("{}",&format!("decimals  is {}", token.token_decimals()));
 
-            assert_eq!(token.name(), "SubDAO Token");
-            assert_eq!(token.symbol(), "SDT");
+            assert_eq!(token.token_name(), Some("SubDAO Token".to_string()));
+            assert_eq!(token.token_symbol(), Some("SDT".to_string()));
             assert_eq!(token.total_supply(), 100000000);
-            assert_eq!(token.decimals(), 4);
+            assert_eq!(token.token_decimals(), 4);
         }
 
         #[ink::test]
@@ -296,21 +828,23 @@ mod erc20 {
                 , "SDT".to_string()
                 , 100000000
                 , 4
+                , None
+                , false
                 , accounts.alice
                 );
 
-            ink_env::debug_println!("{}",&token.name());
-            ink_env::debug_println!("{}",&token.symbol());
+            ink_env::debug_println!("{}",&token.token_name().unwrap());
+            ink_env::debug_println!("{}",&token.token_symbol().unwrap());
             ink_env::debug_println!("{}",&format!("total supply is {}", token.total_supply()));
-            ink_env::debug_println!("{}",&format!("decimals  is {}", token.decimals()));
+            ink_env::debug_println!("{}",&format!("decimals  is {}", token.token_decimals()));
 
-            assert_eq!(token.name(), "SubDAO Token");
-            assert_eq!(token.symbol(), "SDT");
+            assert_eq!(token.token_name(), Some("SubDAO Token".to_string()));
+            assert_eq!(token.token_symbol(), Some("SDT".to_string()));
             assert_eq!(token.total_supply(), 100000000);
-            assert_eq!(token.decimals(), 4);
+            assert_eq!(token.token_decimals(), 4);
 
-            let amount: u64 = 99_u64 * 10_u64.pow(token.decimals() as u32);
-            token.transfer(accounts.bob, amount);
+            let amount: Balance = 99 * 10_u128.pow(token.token_decimals() as u32);
+            assert_eq!(token.transfer(accounts.bob, amount, Vec::new()), Ok(()));
             
             ink_env::debug_println!("{}",&format!("bob balance  is {}", token.balance_of(accounts.bob)));
             assert_eq!(token.balance_of(accounts.bob), amount);
@@ -318,5 +852,199 @@ mod erc20 {
             ink_env::debug_println!("{}",&format!("alice balance  is {}", token.balance_of(accounts.alice)));
             assert_eq!(token.balance_of(accounts.alice), token.total_supply() - amount);
         }
+
+        #[ink::test]
+        fn transfer_fails_without_balance() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Token".to_string(), "SDT".to_string(), 100, 4, None, false, accounts.alice);
+
+            assert_eq!(token.transfer(accounts.bob, 101, Vec::new()), Err(PSP22Error::InsufficientBalance));
+            assert_eq!(token.transfer(AccountId::default(), 1, Vec::new()), Err(PSP22Error::ZeroRecipientAddress));
+            assert_eq!(token.balance_of(accounts.alice), 100);
+        }
+
+        #[ink::test]
+        fn burn_works() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Token".to_string(), "SDT".to_string(), 100, 4, None, false, accounts.alice);
+
+            assert_eq!(token.burn(30), Ok(()));
+            assert_eq!(token.burn(71), Err(PSP22Error::InsufficientBalance));
+            assert_eq!(token.total_supply(), 70);
+            assert_eq!(token.balance_of(accounts.alice), 70);
+
+            // alice is the owner, she can burn bob's tokens but not without allowance
+            assert_eq!(token.transfer(accounts.bob, 20, Vec::new()), Ok(()));
+            assert_eq!(token.burn_from(accounts.bob, 5), Err(PSP22Error::InsufficientAllowance));
+            assert_eq!(token.admin_burn(accounts.bob, 5), Ok(()));
+            assert_eq!(token.balance_of(accounts.bob), 15);
+            assert_eq!(token.total_supply(), 65);
+        }
+
+        #[ink::test]
+        fn supply_cap_works() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Token".to_string(), "SDT".to_string(), 100, 4, Some(150), false, accounts.alice);
+
+            assert_eq!(token.remaining_mintable(), Some(50));
+            assert_eq!(token.add_minter(accounts.bob), Ok(()));
+            assert!(token.is_minter(accounts.bob));
+            assert_eq!(token.mint(accounts.bob, 51), Err(PSP22Error::Custom(String::from("max supply exceeded"))));
+            assert_eq!(token.mint(accounts.bob, 50), Ok(()));
+            assert_eq!(token.remaining_mintable(), Some(0));
+            assert_eq!(token.remove_minter(accounts.bob), Ok(()));
+            assert!(!token.is_minter(accounts.bob));
+        }
+
+        #[ink::test]
+        fn permit_rejects_bad_signature() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Token".to_string(), "SDT".to_string(), 100, 4, None, false, accounts.alice);
+
+            assert!(token.permit(accounts.alice, accounts.bob, 10, u64::MAX, [0u8; 65]).is_err());
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(token.nonces(accounts.alice), 0);
+        }
+
+        /// The substrate ecdsa account of `secret`, the blake2 hash of its compressed public key.
+        fn ecdsa_account(secret: [u8; 32]) -> AccountId {
+            let secp = secp256k1::Secp256k1::new();
+            let secret_key = secp256k1::SecretKey::from_slice(&secret).expect("invalid secret key");
+            let public_key = secp256k1::PublicKey::from_secret_key(&secp, &secret_key).serialize();
+            let mut account = [0u8; 32];
+            ink_env::hash_bytes::<Blake2x256>(&public_key, &mut account);
+            AccountId::from(account)
+        }
+
+        fn sign(secret: [u8; 32], message_hash: [u8; 32]) -> [u8; 65] {
+            let secp = secp256k1::Secp256k1::new();
+            let secret_key = secp256k1::SecretKey::from_slice(&secret).expect("invalid secret key");
+            let message = secp256k1::Message::from_slice(&message_hash).expect("invalid message");
+            let (recovery_id, compact) = secp.sign_recoverable(&message, &secret_key).serialize_compact();
+            let mut signature = [0u8; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = recovery_id.to_i32() as u8;
+            signature
+        }
+
+        #[ink::test]
+        fn permit_works() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Token".to_string(), "SDT".to_string(), 100, 4, None, false, accounts.alice);
+            let owner = ecdsa_account([0x11; 32]);
+
+            let signature = sign([0x11; 32], token.permit_hash(owner, accounts.bob, 10, u64::MAX));
+            assert_eq!(token.permit(owner, accounts.bob, 10, u64::MAX, signature), Ok(()));
+            assert_eq!(token.allowance(owner, accounts.bob), 10);
+            assert_eq!(token.nonces(owner), 1);
+
+            // the nonce moved on, the same permit can't be replayed
+            assert!(token.permit(owner, accounts.bob, 10, u64::MAX, signature).is_err());
+            assert_eq!(token.nonces(owner), 1);
+
+            // a signature for another spender doesn't work for bob
+            let signature = sign([0x11; 32], token.permit_hash(owner, accounts.charlie, 20, u64::MAX));
+            assert!(token.permit(owner, accounts.bob, 20, u64::MAX, signature).is_err());
+            assert_eq!(token.allowance(owner, accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn permit_rejects_expired() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Token".to_string(), "SDT".to_string(), 100, 4, None, false, accounts.alice);
+            let owner = ecdsa_account([0x11; 32]);
+
+            let deadline = ink_env::block_timestamp::<ink_env::DefaultEnvironment>().expect("Cannot get block timestamp");
+            let signature = sign([0x11; 32], token.permit_hash(owner, accounts.bob, 10, deadline));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().expect("Cannot advance block");
+            assert_eq!(token.permit(owner, accounts.bob, 10, deadline, signature),
+                       Err(PSP22Error::Custom(String::from("permit expired"))));
+            assert_eq!(token.allowance(owner, accounts.bob), 0);
+            assert_eq!(token.nonces(owner), 0);
+        }
+
+        #[ink::test]
+        fn soulbound_works() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Rep".to_string(), "REP".to_string(), 100, 0, None, true, accounts.alice);
+
+            let soulbound = Err(PSP22Error::Custom(String::from("token is soulbound")));
+            assert_eq!(token.transfer(accounts.bob, 1, Vec::new()), soulbound);
+            assert_eq!(token.approve(accounts.bob, 1), soulbound);
+            assert_eq!(token.transfer_from(accounts.alice, accounts.bob, 1, Vec::new()), soulbound);
+            assert_eq!(token.mint(accounts.bob, 10), Ok(()));
+            assert_eq!(token.admin_burn(accounts.bob, 4), Ok(()));
+            assert_eq!(token.balance_of(accounts.bob), 6);
+        }
+
+        #[ink::test]
+        fn batch_works() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Token".to_string(), "SDT".to_string(), 100, 4, Some(200), false, accounts.alice);
+
+            // too much in total, nobody receives anything
+            assert_eq!(token.batch_transfer(vec![(accounts.bob, 60), (accounts.charlie, 41)]), Err(PSP22Error::InsufficientBalance));
+            assert_eq!(token.balance_of(accounts.bob), 0);
+
+            assert_eq!(token.batch_transfer(vec![(accounts.bob, 60), (accounts.charlie, 40)]), Ok(()));
+            assert_eq!(token.balance_of(accounts.charlie), 40);
+            assert_eq!(token.balance_of(accounts.alice), 0);
+
+            assert!(token.batch_mint(vec![(accounts.bob, 50), (accounts.django, 51)]).is_err());
+            assert_eq!(token.batch_mint(vec![(accounts.bob, 50), (accounts.django, 50)]), Ok(()));
+            assert_eq!(token.balance_of(accounts.django), 50);
+            assert_eq!(token.total_supply(), 200);
+        }
+
+        #[ink::test]
+        fn pause_and_freeze_works() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Token".to_string(), "SDT".to_string(), 100, 4, None, false, accounts.alice);
+
+            assert_eq!(token.pause(), Ok(()));
+            assert!(token.query_info().paused);
+            assert_eq!(token.transfer(accounts.bob, 1, Vec::new()), Err(PSP22Error::Custom(String::from("token is paused"))));
+            assert_eq!(token.unpause(), Ok(()));
+
+            assert_eq!(token.freeze(accounts.bob), Ok(()));
+            assert_eq!(token.query_info().frozen, vec![accounts.bob]);
+            assert_eq!(token.transfer(accounts.bob, 1, Vec::new()), Err(PSP22Error::Custom(String::from("account is frozen"))));
+            assert_eq!(token.unfreeze(accounts.bob), Ok(()));
+            assert_eq!(token.transfer(accounts.bob, 1, Vec::new()), Ok(()));
+        }
+
+        #[ink::test]
+        fn batch_rejects_frozen_recipient() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Token".to_string(), "SDT".to_string(), 100, 0, None, false, accounts.alice);
+
+            assert_eq!(token.freeze(accounts.charlie), Ok(()));
+            let recipients = vec![(accounts.bob, 10), (accounts.charlie, 10), (accounts.django, 10)];
+            assert_eq!(token.batch_transfer(recipients.clone()), Err(PSP22Error::Custom(String::from("account is frozen"))));
+            // the transfer before the frozen recipient didn't go through either
+            assert_eq!(token.balance_of(accounts.bob), 0);
+            assert_eq!(token.balance_of(accounts.alice), 100);
+
+            assert_eq!(token.pause(), Ok(()));
+            assert_eq!(token.unfreeze(accounts.charlie), Ok(()));
+            assert_eq!(token.batch_transfer(recipients.clone()), Err(PSP22Error::Custom(String::from("token is paused"))));
+            assert_eq!(token.balance_of(accounts.bob), 0);
+            assert_eq!(token.unpause(), Ok(()));
+            assert_eq!(token.batch_transfer(recipients), Ok(()));
+            assert_eq!(token.balance_of(accounts.alice), 70);
+        }
+
+        #[ink::test]
+        fn allowance_works() {
+            let accounts =ink_env::test::default_accounts::<ink_env::DefaultEnvironment>().expect("Cannot get accounts");
+            let mut token = Erc20::new("SubDAO Token".to_string(), "SDT".to_string(), 100, 4, None, false, accounts.alice);
+
+            assert_eq!(token.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(token.increase_allowance(accounts.bob, 5), Ok(()));
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 15);
+            assert_eq!(token.decrease_allowance(accounts.bob, 16), Err(PSP22Error::InsufficientAllowance));
+            assert_eq!(token.decrease_allowance(accounts.bob, 5), Ok(()));
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 10);
+        }
     }
 }
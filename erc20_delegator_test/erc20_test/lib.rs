@@ -7,6 +7,7 @@ pub use self::erc20_test::Erc20Test;
 mod erc20_test {
 
     use erc20::Erc20;
+    use ink_prelude::vec::Vec;
 
     /// Defines the storage of your contract.
     /// Add new fields to the below struct in order
@@ -26,8 +27,8 @@ mod erc20_test {
         }
 
         #[ink(message)]
-        pub fn transfer_in_erc20(&mut self, to: AccountId, value: u64) -> bool {
-            self.erc20.transfer(to, value)
+        pub fn transfer_in_erc20(&mut self, to: AccountId, value: Balance) -> bool {
+            self.erc20.transfer(to, value, Vec::new()).is_ok()
         }
     }
 }
@@ -29,6 +29,7 @@ mod erc20_delegator_test {
     };
     // use ink_prelude::string::String;
     use erc20::Erc20;
+    use ink_prelude::vec::Vec;
     use erc20_test::Erc20Test;
 
     /// Indicates whether a transaction is already confirmed or needs further confirmations.
@@ -132,13 +133,13 @@ mod erc20_delegator_test {
         }
 
         #[ink(message)]
-        pub fn transfer(&mut self, index: u64, to: AccountId, value: u64) -> bool {
+        pub fn transfer(&mut self, index: u64, to: AccountId, value: Balance) -> bool {
             let instance = self.instance_map.get_mut(&index).unwrap();
-            instance.erc20.transfer(to, value)
+            instance.erc20.transfer(to, value, Vec::new()).is_ok()
         }
 
         #[ink(message)]
-        pub fn transfer_by_erc20_test_in_erc20(&mut self, index: u64, to: AccountId, value: u64) -> bool {
+        pub fn transfer_by_erc20_test_in_erc20(&mut self, index: u64, to: AccountId, value: Balance) -> bool {
             let instance = self.instance_map.get_mut(&index).unwrap();
             instance.erc20_test.transfer_in_erc20(to, value)
         }
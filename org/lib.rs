@@ -3,6 +3,12 @@
 extern crate alloc;
 use ink_lang as ink;
 pub use self::org::OrgManager;
+pub use self::org::Role;
+pub use self::org::Application;
+pub use self::org::AdmissionPolicy;
+pub use self::org::TokenGate;
+pub use self::org::MembershipTerms;
+pub use self::org::Invite;
 
 #[ink::contract]
 mod org {
@@ -13,15 +19,133 @@ mod org {
     use ink_storage::{
         collections::HashMap as StorageHashMap,
     };
+    use ink_storage::traits::{PackedLayout, SpreadLayout};
+    use ink_env::call::{build_call, utils::ReturnType, ExecutionInput, Selector};
+    use ink_env::hash::Blake2x256;
 
 
     use auth::Auth;
+    use erc20::Erc20;
+
+    // `VoteManager::new_admission_vote`, called by selector since vote_manager depends on this crate.
+    const NEW_ADMISSION_VOTE_SELECTOR: [u8; 4] = [0x71, 0x3e, 0x0b, 0xd2];
+    // `VoteManager::has_vote_passed`
+    const HAS_VOTE_PASSED_SELECTOR: [u8; 4] = [0x4e, 0x2f, 0x81, 0xa6];
+    // `Vault::deposit`, the vault depends on this crate as well.
+    const VAULT_DEPOSIT_SELECTOR: [u8; 4] = [0x3b, 0x7d, 0x2a, 0x64];
+    // `vault::Asset::Native` is the first variant, scale encoded as its index.
+    const VAULT_NATIVE_ASSET: u8 = 0;
+
+    /// A DAO-defined role, holders are granted every auth action of the role.
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Role {
+        name: String,
+        // (contract_name, function_name) of the auth actions
+        actions: alloc::vec::Vec<(String, String)>,
+    }
+
+    /// A membership application and its outcome.
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Application {
+        application_id: u64,
+        applicant: AccountId,
+        name: String,
+        // hash of the applicant's statement or profile
+        profile: Hash,
+        apply_time: u64,
+        status: u8,// 0: pending 1: approved 2: rejected 3: withdrawn
+        reviewer: Option<AccountId>,
+        reason: Option<String>,
+        review_time: Option<u64>,
+        // the admission vote, when applications are put to a vote
+        vote_id: Option<u64>,
+        // native deposit paid with an application which is put to a vote
+        deposit: Balance,
+    }
+
+    /// Parameters of the vote opened for every application when admission is by vote.
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct AdmissionPolicy {
+        vote_time: u64,
+        support_require_num: u64,
+        min_require_num: u64,
+        // native deposit an applicant pays for opening a vote, refunded on admission
+        deposit: Balance,
+    }
+
+    /// Holding at least `threshold` of `token` lets an account claim membership.
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct TokenGate {
+        token: AccountId,
+        threshold: Balance,
+    }
+
+    /// Membership dues, a member pays `fee` of `asset` (`None` for the native token)
+    /// into the vault for every `period`. Voting stops `grace_period` after expiry.
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct MembershipTerms {
+        asset: Option<AccountId>,
+        fee: Balance,
+        period: u64,
+        grace_period: u64,
+    }
+
+    /// An invite registered by a moderator, the secret's blake2x256 hash is the commitment.
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Invite {
+        commitment: Hash,
+        creator: AccountId,
+        max_uses: u32,
+        uses: u32,
+        expire_time: u64,
+        create_time: u64,
+    }
+
     #[ink(storage)]
     pub struct OrgManager {
 
         moderators: StorageHashMap<AccountId, String>,
         members: StorageHashMap<AccountId, String>,
-        applying_members: StorageHashMap<AccountId, String>,
+        // applicant -> pending application id
+        applying_members: StorageHashMap<AccountId, u64>,
+        applications: StorageHashMap<u64, Application>,
+        application_count: u64,
         owner: AccountId,
         org_id:u64,
         can_free_add_member: bool,
@@ -29,6 +153,26 @@ mod org {
         is_moderator:bool,
         is_owner:bool,
         auth_contract_address:AccountId,
+        roles: StorageHashMap<String, Role>,
+        // (role name, holder) -> who assigned the role
+        role_members: StorageHashMap<(String, AccountId), AccountId>,
+        creator: AccountId,
+        vote_contract_address: Option<AccountId>,
+        admission_policy: Option<AdmissionPolicy>,
+        token_gate: Option<TokenGate>,
+        // members who joined by holding the gate token -> claim time
+        claimed_members: StorageHashMap<AccountId, u64>,
+        vault_contract_address: Option<AccountId>,
+        membership_terms: Option<MembershipTerms>,
+        // member -> membership expiry, members without one don't expire
+        expiries: StorageHashMap<AccountId, u64>,
+        invites: StorageHashMap<Hash, Invite>,
+        // (commitment, member) -> redeem time
+        invite_uses: StorageHashMap<(Hash, AccountId), u64>,
+        // invite_claim_hash(secret, account) -> block the claim was committed in
+        invite_claims: StorageHashMap<Hash, BlockNumber>,
+        // (account, contract_name, function_name) -> grant time, the auth actions the org granted
+        org_grants: StorageHashMap<(AccountId, String, String), u64>,
     }
 
 
@@ -56,6 +200,8 @@ mod org {
         moderator: AccountId,
         #[ink(topic)]
         org_id:u64,
+        #[ink(topic)]
+        remover: AccountId,
     }
 
 
@@ -73,6 +219,24 @@ mod org {
         member: AccountId,
         #[ink(topic)]
         org_id:u64,
+        #[ink(topic)]
+        remover: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ResignDAOMemberEvent {
+        #[ink(topic)]
+        member: AccountId,
+        #[ink(topic)]
+        org_id:u64,
+    }
+
+    #[ink(event)]
+    pub struct ResignDAOModeratorEvent {
+        #[ink(topic)]
+        moderator: AccountId,
+        #[ink(topic)]
+        org_id:u64,
     }
 
 
@@ -85,6 +249,105 @@ mod org {
         org_id:u64,
     }
 
+    #[ink(event)]
+    pub struct RejectDAOMemberEvent {
+        #[ink(topic)]
+        member: AccountId,
+        #[ink(topic)]
+        org_id:u64,
+        #[ink(topic)]
+        rejecter: AccountId,
+        reason: String,
+    }
+
+    #[ink(event)]
+    pub struct AdmissionVoteEvent {
+        #[ink(topic)]
+        member: AccountId,
+        #[ink(topic)]
+        org_id:u64,
+        vote_id: u64,
+    }
+
+    #[ink(event)]
+    pub struct ClaimMembershipEvent {
+        #[ink(topic)]
+        member: AccountId,
+        #[ink(topic)]
+        org_id:u64,
+        balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct PruneMemberEvent {
+        #[ink(topic)]
+        member: AccountId,
+        #[ink(topic)]
+        org_id:u64,
+        #[ink(topic)]
+        pruner: AccountId,
+        balance: Balance,
+    }
+
+    #[ink(event)]
+    pub struct RenewMembershipEvent {
+        #[ink(topic)]
+        member: AccountId,
+        #[ink(topic)]
+        org_id:u64,
+        expiry: u64,
+        fee: Balance,
+    }
+
+    #[ink(event)]
+    pub struct MembershipLapsedEvent {
+        #[ink(topic)]
+        member: AccountId,
+        #[ink(topic)]
+        org_id:u64,
+        expiry: u64,
+    }
+
+    #[ink(event)]
+    pub struct CreateInviteEvent {
+        #[ink(topic)]
+        commitment: Hash,
+        #[ink(topic)]
+        org_id:u64,
+        #[ink(topic)]
+        creator: AccountId,
+        max_uses: u32,
+        expire_time: u64,
+    }
+
+    #[ink(event)]
+    pub struct RevokeInviteEvent {
+        #[ink(topic)]
+        commitment: Hash,
+        #[ink(topic)]
+        org_id:u64,
+        revoker: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RedeemInviteEvent {
+        #[ink(topic)]
+        commitment: Hash,
+        #[ink(topic)]
+        org_id:u64,
+        #[ink(topic)]
+        member: AccountId,
+        uses: u32,
+    }
+
+    #[ink(event)]
+    pub struct WithdrawApplicationEvent {
+        #[ink(topic)]
+        member: AccountId,
+        #[ink(topic)]
+        org_id:u64,
+    }
+
     #[ink(event)]
     pub struct ApproveDAOMemberEvent {
         #[ink(topic)]
@@ -96,6 +359,38 @@ mod org {
     }
 
 
+    #[ink(event)]
+    pub struct CreateRoleEvent {
+        #[ink(topic)]
+        org_id:u64,
+        name: String,
+    }
+
+    #[ink(event)]
+    pub struct DeleteRoleEvent {
+        #[ink(topic)]
+        org_id:u64,
+        name: String,
+    }
+
+    #[ink(event)]
+    pub struct AssignRoleEvent {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        org_id:u64,
+        role: String,
+    }
+
+    #[ink(event)]
+    pub struct UnassignRoleEvent {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        org_id:u64,
+        role: String,
+    }
+
     impl OrgManager {
 
         #[ink(constructor)]
@@ -106,11 +401,27 @@ mod org {
                 moderators: StorageHashMap::default(),
                 members: StorageHashMap::default(),
                 applying_members: StorageHashMap::default(),
+                applications: StorageHashMap::default(),
+                application_count: 0,
                 auth_contract_address:auth_contract_address,
                 can_free_add_member: false,
                 is_member: false,
                 is_moderator:false,
                 is_owner:false,
+                roles: StorageHashMap::default(),
+                role_members: StorageHashMap::default(),
+                creator: Self::env().caller(),
+                vote_contract_address: None,
+                admission_policy: None,
+                token_gate: None,
+                claimed_members: StorageHashMap::default(),
+                vault_contract_address: None,
+                membership_terms: None,
+                expiries: StorageHashMap::default(),
+                invites: StorageHashMap::default(),
+                invite_uses: StorageHashMap::default(),
+                invite_claims: StorageHashMap::default(),
+                org_grants: StorageHashMap::default(),
             }
         }
 
@@ -127,6 +438,7 @@ mod org {
 
         #[ink(message)]
         pub fn set_can_free_add_member(&mut self,can_free_add_member:bool) -> bool {
+            self.ensure_not_paused();
             self.can_free_add_member = can_free_add_member;
             self.can_free_add_member
         }
@@ -172,12 +484,15 @@ mod org {
         }
 
 
+        /// Members whose membership lapsed stay in storage until they renew, they are left out here.
         #[ink(message)]
         pub fn get_dao_members_list(&self) -> alloc::vec::Vec<AccountId> {
             self.members.keys();
             let mut v:alloc::vec::Vec<AccountId> = alloc::vec::Vec::new();
             for key in self.members.keys() {
-                v.push(*key)
+                if !self.is_lapsed(*key) {
+                    v.push(*key)
+                }
             }
             v
         }
@@ -197,11 +512,15 @@ mod org {
 
         }
 
+        /// Lapsed members are left out, like in `get_dao_members_list`.
         #[ink(message)]
         pub fn get_dao_member_detail_list(&self) -> alloc::vec::Vec<(AccountId, String)> {
             self.members.keys();
             let mut v:alloc::vec::Vec<(AccountId, String)> = alloc::vec::Vec::new();
             for key in self.members.keys() {
+                if self.is_lapsed(*key) {
+                    continue;
+                }
 
                 let value = self.members.get(key).unwrap().clone();
 
@@ -218,7 +537,8 @@ mod org {
             let mut v:alloc::vec::Vec<(AccountId, String)> = alloc::vec::Vec::new();
             for key in self.applying_members.keys() {
 
-                let value = self.applying_members.get(key).unwrap().clone();
+                let application_id = self.applying_members.get(key).unwrap();
+                let value = self.applications.get(application_id).unwrap().name.clone();
 
                 v.push((*key,value))
             }
@@ -230,6 +550,7 @@ mod org {
         // FIXME: this implementation is incorrect when the added one is already a member of the dao.
         #[ink(message)]
         pub fn add_dao_moderator(&mut self,name:String,moderator: AccountId) -> bool  {
+            self.ensure_not_paused();
             let caller = self.env().caller();
 
             
@@ -239,10 +560,9 @@ mod org {
 
 
 
-            let  mut auth_instance = self.get_auth_by_address(self.auth_contract_address);
-           // assert!(self.owner == caller || self._has_permission(caller, String::from("auth"),String::from("grant")));
-            auth_instance.grant_permission(moderator, String::from("vote"), String::from("new"));
-            auth_instance.grant_permission(moderator, String::from("vote"), String::from("vote"));
+            for action in Self::tier_actions() {
+                self.grant_org_action(moderator, action);
+            }
 
 
             match self.moderators.insert(moderator,name) {
@@ -260,6 +580,7 @@ mod org {
 
         #[ink(message)]
         pub fn add_dao_moderator_without_grant(&mut self,name:String,moderator: AccountId) -> bool  {
+            self.ensure_not_paused();
             let caller = self.env().caller();
 
 
@@ -267,6 +588,12 @@ mod org {
                 return false;
             }
 
+            // the dao manager grants the tier actions itself, they still belong to the seat
+            let grant_time = self.env().block_timestamp();
+            for (contract_name, function_name) in Self::tier_actions() {
+                self.org_grants.insert((moderator, contract_name, function_name), grant_time);
+            }
+
             match self.moderators.insert(moderator,name) {
                 Some(_) => { false},
                 None => {
@@ -282,6 +609,7 @@ mod org {
         // FIXME: this implementation is incorrect when the added one is already a moderator of the dao.
         #[ink(message)]
         pub fn add_dao_member(&mut self,name:String,member: AccountId) -> bool {
+            self.ensure_not_paused();
 
 
             let (_is_member, is_moderator, is_owner) = self.who_am_i();
@@ -292,36 +620,25 @@ mod org {
                 return false
             }
 
-            let  mut auth_instance = self.get_auth_by_address(self.auth_contract_address);
-           // assert!(auth_instance.has_permission(caller, String::from("auth"),String::from("grant")));
-            auth_instance.grant_permission(member, String::from("vote"), String::from("vote"));
-
-            match self.members.insert(member,name) {
-                Some(_) => { false},
-                None => {
-                    let org_id = self.org_id;
-                    self.env().emit_event(AddDAOMemberEvent{
-                        member,
-                        org_id,
-                    });
-                    true
-                }
-            }
+            self.add_dao_member_private(name, member)
 
         }
 
 
         
+        /// Every path that adds a member ends here, new members get an expiry while dues are charged.
         pub fn add_dao_member_private(&mut self,name:String,member: AccountId) -> bool {
 
             let caller = self.env().caller();
-            let  mut auth_instance = self.get_auth_by_address(self.auth_contract_address);
-           // assert!(auth_instance.has_permission(caller, String::from("auth"),String::from("grant")));
-            auth_instance.grant_permission(member, String::from("vote"), String::from("vote"));
+            self.grant_org_action(member, (String::from("vote"), String::from("vote")));
 
             match self.members.insert(member,name) {
                 Some(_) => { false},
                 None => {
+                    if let Some(terms) = &self.membership_terms {
+                        let expiry = self.env().block_timestamp().saturating_add(terms.period);
+                        self.expiries.insert(member, expiry);
+                    }
                     let org_id = self.org_id;
                     self.env().emit_event(AddDAOMemberEvent{
                         member,
@@ -336,6 +653,7 @@ mod org {
 
         #[ink(message)]
         pub fn batch_add_dao_member(&mut self, members:BTreeMap<String, AccountId>) -> bool {
+            self.ensure_not_paused();
             for (name, accountId) in &members {
                 self.add_dao_member(String::from(name),*accountId);
             }
@@ -344,12 +662,14 @@ mod org {
         }
 
 
+        /// By the owner or a holder of the auth `org.remove_member` permission, ie: a passed vote.
         #[ink(message)]
         pub fn remove_dao_moderator(&mut self,member: AccountId) -> bool  {
+            self.ensure_not_paused();
 
             let caller = self.env().caller();
 
-            if caller !=  self.owner {
+            if caller !=  self.owner && !self.has_org_permission(caller, "remove_member") {
                 return false;
             }
 
@@ -357,10 +677,12 @@ mod org {
             match self.moderators.take(&member) {
                 None => { false}
                 Some(_) => {
+                    self.sync_account(member);
                     let org_id = self.org_id;
                     self.env().emit_event(RemoveDAOModeratorEvent{
                         moderator:member,
                         org_id,
+                        remover:caller,
                     });
                     true
                 }
@@ -369,18 +691,26 @@ mod org {
 
         }
 
-        // FIXME: This implementation is incorrect!!! Need re-factor this part, only moderators can remove members
+        /// By moderators, the owner or a holder of the auth `org.remove_member` permission, ie: a passed vote.
         #[ink(message)]
         pub fn remove_dao_member(&mut self, member: AccountId) -> bool  {
+            self.ensure_not_paused();
+
+            let caller = self.env().caller();
 
+            if !self.check_authority(caller) && !self.has_org_permission(caller, "remove_member") {
+                return false;
+            }
 
             match self.members.take(&member) {
                 None => { false}
                 Some(_) => {
+                    self.sync_account(member);
                     let org_id = self.org_id;
                     self.env().emit_event(RemoveDAOMemberEvent{
                         member:member,
                         org_id:org_id,
+                        remover:caller,
                     });
                     true
                 }
@@ -405,12 +735,21 @@ mod org {
         //     return false;
         // }
 
+        /// The caller leaves the org as a member.
         #[ink(message)]
-        pub fn resign_member(&mut self,member: AccountId) -> bool  {
+        pub fn resign_member(&mut self) -> bool  {
+            self.ensure_not_paused();
 
+            let member = self.env().caller();
 
             if self.members.contains_key(&member) {
                 self.members.take(&member);
+                self.sync_account(member);
+                let org_id = self.org_id;
+                self.env().emit_event(ResignDAOMemberEvent{
+                    member,
+                    org_id,
+                });
                 return true;
             };
 
@@ -418,16 +757,53 @@ mod org {
         }
 
 
+        /// The caller steps down as a moderator.
         #[ink(message)]
-        pub fn resign_moderator(&mut self,moderator: AccountId) -> bool  {
+        pub fn resign_moderator(&mut self) -> bool  {
+            self.ensure_not_paused();
+
+            let moderator = self.env().caller();
 
             if self.moderators.contains_key(&moderator) {
                 self.moderators.take(&moderator);
+                self.sync_account(moderator);
+                let org_id = self.org_id;
+                self.env().emit_event(ResignDAOModeratorEvent{
+                    moderator,
+                    org_id,
+                });
                 return true;
             };
             return false;
         }
 
+        /// `None` while no auth contract is linked (the default address), permissions are then
+        /// neither checked nor granted.
+        fn auth(&self) -> Option<Auth> {
+            if self.auth_contract_address == AccountId::default() {
+                return None;
+            }
+            Some(self.get_auth_by_address(self.auth_contract_address))
+        }
+
+        fn is_dao_paused(&self) -> bool {
+            self.auth().map_or(false, |auth_instance| auth_instance.is_paused())
+        }
+
+        /// Every message which changes the org reverts while the DAO is paused, this also
+        /// returns the value sent with the payable ones.
+        fn ensure_not_paused(&self) {
+            assert!(!self.is_dao_paused(), "dao is paused");
+        }
+
+        fn has_org_permission(&self, caller: AccountId, function_name: &str) -> bool {
+            match self.auth() {
+                None => false,
+                Some(auth_instance) =>
+                    auth_instance.has_permission(caller, String::from("org"), String::from(function_name)),
+            }
+        }
+
 
 
         #[ink(message)]
@@ -482,6 +858,7 @@ mod org {
 
         #[ink(message)]
         pub fn transfer_ownership(&mut self,new_owner: AccountId) -> bool  {
+            self.ensure_not_paused();
 
             let caller = self.env().caller();
 
@@ -500,72 +877,1021 @@ mod org {
             self.owner = new_owner;
             return true;
         }
-        #[ink(message)]
-        pub fn apply_member(&mut self,name:String,member: AccountId) -> bool {
-            match self.applying_members.insert(member,name) {
-                Some(_) => { false},
-                None => {
-                    let org_id = self.org_id;
-                    self.env().emit_event(ApplyDAOMemberEvent{
-                        member,
-                        org_id,
-                    });
-                    true
-                }
-            }
 
+        /// The caller applies for membership, `profile` is the hash of a statement or profile.
+        /// While admission is by vote the policy's deposit has to be sent along with the call.
+        #[ink(message, payable)]
+        pub fn apply_member(&mut self,name:String,profile: Hash) -> bool {
+            self.ensure_not_paused();
+            let member = self.env().caller();
+            let transferred = self.env().transferred_balance();
+            if self.applying_members.contains_key(&member)
+                || self.members.contains_key(&member) || self.moderators.contains_key(&member) {
+                // don't keep a deposit sent with a failed application
+                assert!(transferred == 0, "application failed");
+                return false;
+            }
+            let deposit = self.admission_policy.as_ref().map(|policy| policy.deposit).unwrap_or(0);
+            assert!(transferred == deposit, "transferred value mismatch");
+            let application_id = self.application_count + 1;
+            self.application_count = application_id;
+            self.applications.insert(application_id, Application {
+                application_id,
+                applicant: member,
+                name,
+                profile,
+                apply_time: self.env().block_timestamp(),
+                status: 0,
+                reviewer: None,
+                reason: None,
+                review_time: None,
+                vote_id: None,
+                deposit,
+            });
+            self.applying_members.insert(member, application_id);
+            let org_id = self.org_id;
+            self.env().emit_event(ApplyDAOMemberEvent{
+                member,
+                org_id,
+            });
+            if let Some(policy) = self.admission_policy.clone() {
+                let vote_id = self.open_admission_vote(member, application_id, &policy);
+                self.applications.get_mut(&application_id).unwrap().vote_id = Some(vote_id);
+                self.env().emit_event(AdmissionVoteEvent{
+                    member,
+                    org_id,
+                    vote_id,
+                });
+            }
+            true
         }
 
+        fn open_admission_vote(&self, member: AccountId, application_id: u64, policy: &AdmissionPolicy) -> u64 {
+            let vote_contract_address = self.vote_contract_address.expect("vote contract not set");
+            build_call::<ink_env::DefaultEnvironment>()
+                .callee(vote_contract_address)
+                .gas_limit(0)
+                .transferred_value(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(NEW_ADMISSION_VOTE_SELECTOR))
+                        .push_arg(member)
+                        .push_arg(application_id)
+                        .push_arg(policy.vote_time)
+                        .push_arg(policy.support_require_num)
+                        .push_arg(policy.min_require_num)
+                )
+                .returns::<ReturnType<u64>>()
+                .fire()
+                .expect("failed to open the admission vote")
+        }
 
-   
-        pub fn check_authority(&self, caller:AccountId) -> bool {
+        /// Called by the dao manager on init, only the contract which instantiated the org can call it.
+        #[ink(message)]
+        pub fn set_vote_contract(&mut self, vote_contract_address: AccountId) -> bool {
+            if self.env().caller() != self.creator {
+                return false;
+            }
+            self.vote_contract_address = Some(vote_contract_address);
+            true
+        }
 
-                let moderator_list = self.get_dao_moderator_list();
+        /// Owner only, put every new application to a vote instead of moderator approval.
+        #[ink(message)]
+        pub fn set_admission_policy(&mut self, vote_time: u64, support_require_num: u64, min_require_num: u64,
+                                    deposit: Balance) -> bool {
+            self.ensure_not_paused();
+            // forfeited deposits go to the vault
+            if self.env().caller() != self.owner || self.vote_contract_address.is_none()
+                || (deposit > 0 && self.vault_contract_address.is_none()) {
+                return false;
+            }
+            self.admission_policy = Some(AdmissionPolicy {
+                vote_time,
+                support_require_num,
+                min_require_num,
+                deposit,
+            });
+            true
+        }
 
-                if caller == self.owner {
-                    return true;
-                }
-                for key in moderator_list {
-                    let moderator = key;
-                    if caller == moderator {
-                        return true;
-                    }
-                }
+        /// Owner only, go back to moderator approval. Votes already opened still admit on passing.
+        #[ink(message)]
+        pub fn clear_admission_policy(&mut self) -> bool {
+            self.ensure_not_paused();
+            if self.env().caller() != self.owner {
                 return false;
+            }
+            self.admission_policy = None;
+            true
+        }
 
+        #[ink(message)]
+        pub fn get_admission_policy(&self) -> Option<AdmissionPolicy> {
+            self.admission_policy.clone()
         }
 
+        /// Called by the dao manager on init, only the contract which instantiated the org can call it.
+        #[ink(message)]
+        pub fn set_vault_contract(&mut self, vault_contract_address: AccountId) -> bool {
+            if self.env().caller() != self.creator {
+                return false;
+            }
+            self.vault_contract_address = Some(vault_contract_address);
+            true
+        }
 
+        /// Owner only, charge dues for membership. Members who join afterwards get an expiry,
+        /// existing members don't expire until they renew for the first time.
         #[ink(message)]
-        pub fn approve_member(&mut self,name:String,member: AccountId) -> bool {
+        pub fn set_membership_terms(&mut self, asset: Option<AccountId>, fee: Balance, period: u64, grace_period: u64) -> bool {
+            self.ensure_not_paused();
+            if self.env().caller() != self.owner || self.vault_contract_address.is_none() || period == 0 {
+                return false;
+            }
+            self.membership_terms = Some(MembershipTerms {
+                asset,
+                fee,
+                period,
+                grace_period,
+            });
+            true
+        }
 
-            let caller = self.env().caller();
+        /// Owner only, stop charging dues. Recorded expiries are dropped so no membership lapses.
+        #[ink(message)]
+        pub fn clear_membership_terms(&mut self) -> bool {
+            self.ensure_not_paused();
+            if self.env().caller() != self.owner {
+                return false;
+            }
+            self.membership_terms = None;
+            let members: alloc::vec::Vec<AccountId> = self.expiries.keys().cloned().collect();
+            for member in members {
+                self.expiries.take(&member);
+                self.sync_account(member);
+            }
+            true
+        }
 
-            let can_operate = self.check_authority(caller);
+        #[ink(message)]
+        pub fn get_membership_terms(&self) -> Option<MembershipTerms> {
+            self.membership_terms.clone()
+        }
 
-            if can_operate == false {
-                return false;
+        /// The caller pays the dues into the vault and extends their membership by one period,
+        /// counted from the current expiry or from now if it already passed. Native dues are sent
+        /// along with the call, erc20 dues are pulled from the caller's allowance to the org.
+        /// Either way the org deposits them into the vault with the member named in the memo.
+        #[ink(message, payable)]
+        pub fn renew_membership(&mut self) -> bool {
+            self.ensure_not_paused();
+            let member = self.env().caller();
+            let transferred = self.env().transferred_balance();
+            let terms = match self.membership_terms.clone() {
+                None => return false,
+                Some(terms) => terms,
+            };
+            assert!(self.members.contains_key(&member), "not a member");
+            let vault = self.vault_contract_address.expect("vault contract not set");
+            match terms.asset {
+                None => {
+                    assert!(transferred == terms.fee, "transferred value mismatch");
+                    if terms.fee > 0 {
+                        assert!(self.deposit_to_vault(vault, terms.fee, Self::account_memo("membership dues of", member)),
+                                "failed to pay the vault");
+                    }
+                }
+                Some(token) => {
+                    assert!(transferred == 0, "native token sent with erc20 dues");
+                    if terms.fee > 0 {
+                        let mut erc20_instance: Erc20 = ink_env::call::FromAccountId::from_account_id(token);
+                        assert!(erc20_instance.transfer_from(member, self.env().account_id(), terms.fee,
+                                                             alloc::vec::Vec::new()).is_ok(),
+                                "failed to collect the dues");
+                        // recorded by the vault's `on_token_received` with the memo
+                        assert!(erc20_instance.transfer_and_call(vault, terms.fee,
+                                                                 Self::account_memo("membership dues of", member).into_bytes()).is_ok(),
+                                "failed to pay the vault");
+                    }
+                }
             }
+            let now = self.env().block_timestamp();
+            let start = match self.expiries.get(&member) {
+                Some(expiry) if *expiry > now => *expiry,
+                _ => now,
+            };
+            let expiry = start.saturating_add(terms.period);
+            self.expiries.insert(member, expiry);
+            // a lapsed member gets the vote back
+            self.sync_account(member);
+            self.env().emit_event(RenewMembershipEvent{
+                member,
+                org_id: self.org_id,
+                expiry,
+                fee: terms.fee,
+            });
+            true
+        }
 
-            if self.applying_members.contains_key(&member) {
-                let caller_new = self.env().caller();
-                self.add_dao_member_private(name,member);
-                self.applying_members.take(&member);
-                let org_id = self.org_id;
+        /// Native `value` goes through the vault's `deposit`, so it shows up in the vault's ledger.
+        fn deposit_to_vault(&self, vault: AccountId, value: Balance, memo: String) -> bool {
+            build_call::<ink_env::DefaultEnvironment>()
+                .callee(vault)
+                .gas_limit(0)
+                .transferred_value(value)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(VAULT_DEPOSIT_SELECTOR))
+                        .push_arg(VAULT_NATIVE_ASSET)
+                        .push_arg(value)
+                        .push_arg(Some(memo))
+                )
+                .returns::<ReturnType<bool>>()
+                .fire()
+                .unwrap_or(false)
+        }
 
-                self.env().emit_event(ApproveDAOMemberEvent{
-                    member,
-                    org_id,
-                    approver:caller_new,
-                });
+        /// `prefix` followed by the hex encoded account, memos of the org's vault deposits.
+        fn account_memo(prefix: &str, member: AccountId) -> String {
+            let bytes: &[u8; 32] = member.as_ref();
+            let mut memo = String::from(prefix);
+            memo.push_str(" 0x");
+            for byte in bytes.iter() {
+                memo.push_str(&alloc::format!("{:02x}", byte));
+            }
+            memo
+        }
 
-                return true;
+        fn is_lapsed(&self, member: AccountId) -> bool {
+            let grace_period = match &self.membership_terms {
+                None => return false,
+                Some(terms) => terms.grace_period,
             };
-            return false;
+            match self.expiries.get(&member) {
+                None => false,
+                Some(expiry) => self.env().block_timestamp() > expiry.saturating_add(grace_period),
+            }
+        }
 
+        /// Whether `member` is past expiry and grace period, the vote manager refuses their votes
+        /// even before `revoke_lapsed` took the permission.
+        #[ink(message)]
+        pub fn is_lapsed_member(&self, member: AccountId) -> bool {
+            self.members.contains_key(&member) && self.is_lapsed(member)
         }
 
-    }
+        /// Anyone can take the voting permission from a member whose membership lapsed. The member
+        /// is kept with their expiry so they can still renew, but is left out of the member lists.
+        #[ink(message)]
+        pub fn revoke_lapsed(&mut self, member: AccountId) -> bool {
+            self.ensure_not_paused();
+            if !self.members.contains_key(&member) || !self.is_lapsed(member) {
+                return false;
+            }
+            self.sync_account(member);
+            self.env().emit_event(MembershipLapsedEvent{
+                member,
+                org_id: self.org_id,
+                expiry: *self.expiries.get(&member).unwrap(),
+            });
+            true
+        }
+
+        #[ink(message)]
+        pub fn get_membership_expiry(&self, member: AccountId) -> Option<u64> {
+            self.expiries.get(&member).cloned()
+        }
+
+        /// Members whose membership expires within `within` from now or already expired but
+        /// are still in the grace period, with their expiry.
+        #[ink(message)]
+        pub fn get_expiring_members(&self, within: u64) -> alloc::vec::Vec<(AccountId, u64)> {
+            let now = self.env().block_timestamp();
+            let mut v:alloc::vec::Vec<(AccountId, u64)> = alloc::vec::Vec::new();
+            for (member, expiry) in self.expiries.iter() {
+                if *expiry <= now.saturating_add(within) && !self.is_lapsed(*member) {
+                    v.push((*member, *expiry))
+                }
+            }
+            v
+        }
+
+        /// Members past the expiry and the grace period, with their expiry.
+        #[ink(message)]
+        pub fn get_lapsed_members(&self) -> alloc::vec::Vec<(AccountId, u64)> {
+            let mut v:alloc::vec::Vec<(AccountId, u64)> = alloc::vec::Vec::new();
+            for (member, expiry) in self.expiries.iter() {
+                if self.is_lapsed(*member) {
+                    v.push((*member, *expiry))
+                }
+            }
+            v
+        }
+
+        /// Moderators or owner register an invite, `commitment` is the blake2x256 hash of the secret.
+        #[ink(message)]
+        pub fn create_invite(&mut self, commitment: Hash, max_uses: u32, expire_time: u64) -> bool {
+            self.ensure_not_paused();
+            let caller = self.env().caller();
+            let now = self.env().block_timestamp();
+            if !self.check_authority(caller) || self.invites.contains_key(&commitment)
+                || max_uses == 0 || expire_time <= now {
+                return false;
+            }
+            self.invites.insert(commitment, Invite {
+                commitment,
+                creator: caller,
+                max_uses,
+                uses: 0,
+                expire_time,
+                create_time: now,
+            });
+            self.env().emit_event(CreateInviteEvent{
+                commitment,
+                org_id: self.org_id,
+                creator: caller,
+                max_uses,
+                expire_time,
+            });
+            true
+        }
+
+        /// Moderators or owner stop an invite from being redeemed, its uses stay recorded.
+        #[ink(message)]
+        pub fn revoke_invite(&mut self, commitment: Hash) -> bool {
+            self.ensure_not_paused();
+            let caller = self.env().caller();
+            if !self.check_authority(caller) {
+                return false;
+            }
+            match self.invites.get_mut(&commitment) {
+                None => return false,
+                Some(invite) => invite.max_uses = invite.uses,
+            }
+            self.env().emit_event(RevokeInviteEvent{
+                commitment,
+                org_id: self.org_id,
+                revoker: caller,
+            });
+            true
+        }
+
+        /// The blake2x256 hash of `secret` followed by `account`, what an invitee commits to
+        /// with `commit_invite_claim` before revealing the secret.
+        #[ink(message)]
+        pub fn invite_claim_hash(&self, secret: alloc::vec::Vec<u8>, account: AccountId) -> Hash {
+            let mut input = secret;
+            let account_bytes: &[u8; 32] = account.as_ref();
+            input.extend_from_slice(account_bytes);
+            Self::blake2x256(&input)
+        }
+
+        fn blake2x256(input: &[u8]) -> Hash {
+            let mut output = <Blake2x256 as ink_env::hash::HashOutput>::Type::default();
+            ink_env::hash_bytes::<Blake2x256>(input, &mut output);
+            Hash::from(output)
+        }
+
+        /// First step of redeeming an invite, `claim` is `invite_claim_hash(secret, caller)`.
+        /// It doesn't reveal the secret, and binds it to the caller's account.
+        #[ink(message)]
+        pub fn commit_invite_claim(&mut self, claim: Hash) -> bool {
+            self.ensure_not_paused();
+            if self.invite_claims.contains_key(&claim) {
+                return false;
+            }
+            let block_number = self.env().block_number();
+            self.invite_claims.insert(claim, block_number);
+            true
+        }
+
+        /// The caller joins as a member by revealing the secret of an unexpired invite with uses
+        /// left. The caller must have committed the claim in an earlier block, so whoever copies
+        /// the secret from a pending redemption is too late to commit their own claim.
+        #[ink(message)]
+        pub fn redeem_invite(&mut self, name: String, secret: alloc::vec::Vec<u8>) -> bool {
+            self.ensure_not_paused();
+            let member = self.env().caller();
+            let commitment = Self::blake2x256(&secret);
+            let claim = self.invite_claim_hash(secret, member);
+            let now = self.env().block_timestamp();
+            let (is_member, is_moderator, is_owner) = self.check_role_by_account(member);
+            if is_member || is_moderator || is_owner {
+                return false;
+            }
+            match self.invite_claims.get(&claim) {
+                Some(block_number) if *block_number < self.env().block_number() => {}
+                _ => return false,
+            }
+            let uses = match self.invites.get_mut(&commitment) {
+                None => return false,
+                Some(invite) => {
+                    if invite.uses >= invite.max_uses || now >= invite.expire_time {
+                        return false;
+                    }
+                    invite.uses += 1;
+                    invite.uses
+                }
+            };
+            self.invite_claims.take(&claim);
+            // a pending application is settled by the invite
+            self.close_application(member, 1, None, None);
+            self.add_dao_member_private(name, member);
+            self.invite_uses.insert((commitment, member), now);
+            self.env().emit_event(RedeemInviteEvent{
+                commitment,
+                org_id: self.org_id,
+                member,
+                uses,
+            });
+            true
+        }
+
+        #[ink(message)]
+        pub fn get_invite(&self, commitment: Hash) -> Option<Invite> {
+            self.invites.get(&commitment).cloned()
+        }
+
+        /// Who redeemed the invite and when.
+        #[ink(message)]
+        pub fn get_invite_uses(&self, commitment: Hash) -> alloc::vec::Vec<(AccountId, u64)> {
+            let mut v:alloc::vec::Vec<(AccountId, u64)> = alloc::vec::Vec::new();
+            for ((used_commitment, member), redeem_time) in self.invite_uses.iter() {
+                if *used_commitment == commitment {
+                    v.push((*member, *redeem_time))
+                }
+            }
+            v.sort_by(|a, b| a.1.cmp(&b.1));
+            v
+        }
+
+        /// Owner only, let holders of at least `threshold` of `token` claim membership.
+        #[ink(message)]
+        pub fn set_token_gate(&mut self, token: AccountId, threshold: Balance) -> bool {
+            self.ensure_not_paused();
+            if self.env().caller() != self.owner || threshold == 0 {
+                return false;
+            }
+            self.token_gate = Some(TokenGate {
+                token,
+                threshold,
+            });
+            true
+        }
+
+        /// Owner only, close the token gate. Members who already claimed stay.
+        #[ink(message)]
+        pub fn clear_token_gate(&mut self) -> bool {
+            self.ensure_not_paused();
+            if self.env().caller() != self.owner {
+                return false;
+            }
+            self.token_gate = None;
+            true
+        }
+
+        #[ink(message)]
+        pub fn get_token_gate(&self) -> Option<TokenGate> {
+            self.token_gate.clone()
+        }
+
+        fn gate_balance(&self, gate: &TokenGate, account: AccountId) -> Balance {
+            let erc20_instance: Erc20 = ink_env::call::FromAccountId::from_account_id(gate.token);
+            erc20_instance.balance_of(account)
+        }
+
+        /// The caller joins as a member if they hold enough of the gate token.
+        #[ink(message)]
+        pub fn claim_membership(&mut self, name: String) -> bool {
+            self.ensure_not_paused();
+            let member = self.env().caller();
+            let gate = match self.token_gate.clone() {
+                None => return false,
+                Some(gate) => gate,
+            };
+            let (is_member, is_moderator, is_owner) = self.check_role_by_account(member);
+            if is_member || is_moderator || is_owner {
+                return false;
+            }
+            let balance = self.gate_balance(&gate, member);
+            if balance < gate.threshold {
+                return false;
+            }
+            // a pending application is settled by the claim
+            self.close_application(member, 1, None, None);
+            self.add_dao_member_private(name, member);
+            self.claimed_members.insert(member, self.env().block_timestamp());
+            self.env().emit_event(ClaimMembershipEvent{
+                member,
+                org_id: self.org_id,
+                balance,
+            });
+            true
+        }
+
+        /// Anyone can remove a member who claimed membership and no longer holds enough of the gate token.
+        #[ink(message)]
+        pub fn prune(&mut self, member: AccountId) -> bool {
+            self.ensure_not_paused();
+            let gate = match self.token_gate.clone() {
+                None => return false,
+                Some(gate) => gate,
+            };
+            if !self.claimed_members.contains_key(&member) {
+                return false;
+            }
+            let balance = self.gate_balance(&gate, member);
+            if balance >= gate.threshold {
+                return false;
+            }
+            self.members.take(&member);
+            self.sync_account(member);
+            self.env().emit_event(PruneMemberEvent{
+                member,
+                org_id: self.org_id,
+                pruner: self.env().caller(),
+                balance,
+            });
+            true
+        }
+
+        #[ink(message)]
+        pub fn get_claimed_members(&self) -> alloc::vec::Vec<AccountId> {
+            self.claimed_members.keys().cloned().collect()
+        }
+
+        /// Admits a pending applicant under the applied name, needs the `org.admit_member` permission.
+        /// Called by the vote manager when the admission vote passes, the vote is for one
+        /// application so a later application of the same account isn't admitted by it.
+        #[ink(message)]
+        pub fn admit_member(&mut self, member: AccountId, application_id: u64) -> bool {
+            self.ensure_not_paused();
+            let caller = self.env().caller();
+            if !self.has_org_permission(caller, "admit_member") {
+                return false;
+            }
+            if self.applying_members.get(&member) != Some(&application_id) {
+                return false;
+            }
+            let name = self.applications.get(&application_id).unwrap().name.clone();
+            self.add_dao_member_private(name, member);
+            self.close_application(member, 1, Some(caller), None);
+            let org_id = self.org_id;
+            self.env().emit_event(ApproveDAOMemberEvent{
+                member,
+                org_id,
+                approver: caller,
+            });
+            true
+        }
+
+        /// The applicant withdraws a pending application.
+        #[ink(message)]
+        pub fn withdraw_application(&mut self) -> bool {
+            self.ensure_not_paused();
+            let member = self.env().caller();
+            if !self.close_application(member, 3, None, None) {
+                return false;
+            }
+            let org_id = self.org_id;
+            self.env().emit_event(WithdrawApplicationEvent{
+                member,
+                org_id,
+            });
+            true
+        }
+
+        /// Moderators or owner reject a pending application with a reason.
+        #[ink(message)]
+        pub fn reject_member(&mut self,member: AccountId,reason: String) -> bool {
+            self.ensure_not_paused();
+            let caller = self.env().caller();
+            if !self.check_authority(caller) {
+                return false;
+            }
+            if !self.close_application(member, 2, Some(caller), Some(reason.clone())) {
+                return false;
+            }
+            let org_id = self.org_id;
+            self.env().emit_event(RejectDAOMemberEvent{
+                member,
+                org_id,
+                rejecter: caller,
+                reason,
+            });
+            true
+        }
+
+        fn close_application(&mut self, member: AccountId, status: u8,
+                             reviewer: Option<AccountId>, reason: Option<String>) -> bool {
+            let application_id = match self.applying_members.take(&member) {
+                None => return false,
+                Some(application_id) => application_id,
+            };
+            let review_time = self.env().block_timestamp();
+            let application = self.applications.get_mut(&application_id).unwrap();
+            application.status = status;
+            application.reviewer = reviewer;
+            application.reason = reason;
+            application.review_time = Some(review_time);
+            let deposit = application.deposit;
+            // the deposit comes back on admission or a withdrawal before any vote was opened
+            let refund = status == 1 || (status == 3 && application.vote_id.is_none());
+            if deposit > 0 && refund {
+                assert!(self.env().transfer(member, deposit).is_ok(), "failed to return the deposit");
+            } else if deposit > 0 {
+                let vault = self.vault_contract_address.expect("vault contract not set");
+                assert!(self.deposit_to_vault(vault, deposit, Self::account_memo("application deposit of", member)),
+                        "failed to pay the vault");
+            }
+            true
+        }
+
+        /// Anyone can close a pending application whose admission vote ended without passing,
+        /// the deposit goes to the vault. Passed votes admit the applicant once executed.
+        #[ink(message)]
+        pub fn close_failed_application(&mut self, member: AccountId) -> bool {
+            self.ensure_not_paused();
+            let vote_id = match self.applying_members.get(&member) {
+                None => return false,
+                Some(application_id) => match self.applications.get(application_id).unwrap().vote_id {
+                    None => return false,
+                    Some(vote_id) => vote_id,
+                },
+            };
+            if self.admission_vote_passed(vote_id) != Some(false) {
+                return false;
+            }
+            let caller = self.env().caller();
+            let reason = String::from("admission vote failed");
+            self.close_application(member, 2, Some(caller), Some(reason.clone()));
+            self.env().emit_event(RejectDAOMemberEvent{
+                member,
+                org_id: self.org_id,
+                rejecter: caller,
+                reason,
+            });
+            true
+        }
+
+        fn admission_vote_passed(&self, vote_id: u64) -> Option<bool> {
+            let vote_contract_address = self.vote_contract_address.expect("vote contract not set");
+            build_call::<ink_env::DefaultEnvironment>()
+                .callee(vote_contract_address)
+                .gas_limit(0)
+                .transferred_value(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(HAS_VOTE_PASSED_SELECTOR))
+                        .push_arg(vote_id)
+                )
+                .returns::<ReturnType<Option<bool>>>()
+                .fire()
+                .expect("failed to query the admission vote")
+        }
+
+        #[ink(message)]
+        pub fn get_application(&self, application_id: u64) -> Option<Application> {
+            self.applications.get(&application_id).cloned()
+        }
+
+        /// Every application of `account`, oldest first.
+        #[ink(message)]
+        pub fn get_application_history(&self, account: AccountId) -> alloc::vec::Vec<Application> {
+            let mut v:alloc::vec::Vec<Application> = alloc::vec::Vec::new();
+            for application in self.applications.values() {
+                if application.applicant == account {
+                    v.push(application.clone())
+                }
+            }
+            v.sort_by(|a, b| a.application_id.cmp(&b.application_id));
+            v
+        }
+
+
+   
+        pub fn check_authority(&self, caller:AccountId) -> bool {
+
+                let moderator_list = self.get_dao_moderator_list();
+
+                if caller == self.owner {
+                    return true;
+                }
+                for key in moderator_list {
+                    let moderator = key;
+                    if caller == moderator {
+                        return true;
+                    }
+                }
+                return false;
+
+        }
+
+
+        #[ink(message)]
+        pub fn approve_member(&mut self,name:String,member: AccountId) -> bool {
+            self.ensure_not_paused();
+
+            let caller = self.env().caller();
+
+            let can_operate = self.check_authority(caller);
+
+            // applications are decided by vote while an admission policy is set
+            if can_operate == false || self.admission_policy.is_some() {
+                return false;
+            }
+
+            if self.applying_members.contains_key(&member) {
+                let caller_new = self.env().caller();
+                self.add_dao_member_private(name,member);
+                self.close_application(member, 1, Some(caller_new), None);
+                let org_id = self.org_id;
+
+                self.env().emit_event(ApproveDAOMemberEvent{
+                    member,
+                    org_id,
+                    approver:caller_new,
+                });
+
+                return true;
+            };
+            return false;
+
+        }
+
+        /// Defines a role with the auth actions its holders get. Roles can carry any
+        /// action, so creating one needs the `org.role` permission held by the vote manager.
+        #[ink(message)]
+        pub fn create_role(&mut self, name: String, actions: alloc::vec::Vec<(String, String)>) -> bool {
+            self.ensure_not_paused();
+            let caller = self.env().caller();
+            if !self.has_org_permission(caller, "role") {
+                return false;
+            }
+            self.create_role_private(name, actions)
+        }
+
+        fn create_role_private(&mut self, name: String, actions: alloc::vec::Vec<(String, String)>) -> bool {
+            if self.roles.contains_key(&name) {
+                return false;
+            }
+            self.roles.insert(name.clone(), Role {
+                name: name.clone(),
+                actions,
+            });
+            self.env().emit_event(CreateRoleEvent{
+                org_id: self.org_id,
+                name,
+            });
+            true
+        }
+
+        /// Removes a role, its holders lose the role's auth actions. By owner or a holder of `org.role`.
+        #[ink(message)]
+        pub fn delete_role(&mut self, name: String) -> bool {
+            self.ensure_not_paused();
+            let caller = self.env().caller();
+            if !self.roles.contains_key(&name)
+                || (caller != self.owner && !self.has_org_permission(caller, "role")) {
+                return false;
+            }
+            for account in self.get_role_members(name.clone()) {
+                self.unassign_role_private(name.clone(), account);
+            }
+            self.roles.take(&name);
+            self.env().emit_event(DeleteRoleEvent{
+                org_id: self.org_id,
+                name,
+            });
+            true
+        }
+
+        /// Gives an org account a role and grants its auth actions, by moderators or owner.
+        #[ink(message)]
+        pub fn assign_role(&mut self, name: String, account: AccountId) -> bool {
+            self.ensure_not_paused();
+            let caller = self.env().caller();
+            if !self.check_authority(caller) {
+                return false;
+            }
+            let (is_member, is_moderator, is_owner) = self.check_role_by_account(account);
+            if !is_member && !is_moderator && !is_owner {
+                return false;
+            }
+            let role = match self.roles.get(&name) {
+                None => return false,
+                Some(role) => role.clone(),
+            };
+            if self.role_members.insert((name.clone(), account), caller).is_some() {
+                return false;
+            }
+            for action in role.actions {
+                self.grant_org_action(account, action);
+            }
+            self.env().emit_event(AssignRoleEvent{
+                account,
+                org_id: self.org_id,
+                role: name,
+            });
+            true
+        }
+
+        /// Takes a role away, actions still held through the account's other roles or
+        /// membership are kept. By moderators or owner.
+        #[ink(message)]
+        pub fn unassign_role(&mut self, name: String, account: AccountId) -> bool {
+            self.ensure_not_paused();
+            let caller = self.env().caller();
+            if !self.check_authority(caller) {
+                return false;
+            }
+            self.unassign_role_private(name, account)
+        }
+
+        fn unassign_role_private(&mut self, name: String, account: AccountId) -> bool {
+            if self.role_members.take(&(name.clone(), account)).is_none() {
+                return false;
+            }
+            if let Some(role) = self.roles.get(&name).cloned() {
+                let kept = self.expected_actions(account);
+                for action in role.actions {
+                    if !kept.contains(&action) {
+                        self.revoke_org_action(account, action);
+                    }
+                }
+            }
+            self.env().emit_event(UnassignRoleEvent{
+                account,
+                org_id: self.org_id,
+                role: name,
+            });
+            true
+        }
+
+        /// The auth actions `account` should hold from its tier and roles.
+        fn expected_actions(&self, account: AccountId) -> alloc::vec::Vec<(String, String)> {
+            let mut actions:alloc::vec::Vec<(String, String)> = alloc::vec::Vec::new();
+            let (is_member, is_moderator, is_owner) = self.check_role_by_account(account);
+            if is_moderator || is_owner {
+                actions.push((String::from("vote"), String::from("new")));
+            }
+            if (is_member && !self.is_lapsed(account)) || is_moderator || is_owner {
+                actions.push((String::from("vote"), String::from("vote")));
+            }
+            for name in self.get_account_roles(account) {
+                if let Some(role) = self.roles.get(&name) {
+                    for action in &role.actions {
+                        if !actions.contains(action) {
+                            actions.push(action.clone());
+                        }
+                    }
+                }
+            }
+            actions
+        }
+
+        /// Brings `account`'s org-managed auth grants in line with its membership: the
+        /// `vote.new`/`vote.vote` tier actions and the actions of its roles. Accounts
+        /// which left the org also lose their roles. Only actions the org granted are
+        /// revoked, grants made outside the org are left alone.
+        fn sync_account(&mut self, account: AccountId) {
+            let (is_member, is_moderator, is_owner) = self.check_role_by_account(account);
+            let in_org = is_member || is_moderator || is_owner;
+            if !is_member {
+                self.claimed_members.take(&account);
+                self.expiries.take(&account);
+            }
+            if !in_org {
+                for name in self.get_account_roles(account) {
+                    self.role_members.take(&(name.clone(), account));
+                    self.env().emit_event(UnassignRoleEvent{
+                        account,
+                        org_id: self.org_id,
+                        role: name,
+                    });
+                }
+            }
+
+            let expected = self.expected_actions(account);
+            let mut actions = Self::tier_actions();
+            for action in self.role_actions() {
+                if !actions.contains(&action) {
+                    actions.push(action);
+                }
+            }
+            for action in actions {
+                if expected.contains(&action) {
+                    self.grant_org_action(account, action);
+                } else {
+                    self.revoke_org_action(account, action);
+                }
+            }
+        }
+
+        fn grant_org_action(&mut self, account: AccountId, action: (String, String)) {
+            if let Some(mut auth_instance) = self.auth() {
+                auth_instance.grant_permission(account, action.0.clone(), action.1.clone());
+            }
+            let grant_time = self.env().block_timestamp();
+            self.org_grants.insert((account, action.0, action.1), grant_time);
+        }
+
+        /// Revokes `action` only if the org granted it.
+        fn revoke_org_action(&mut self, account: AccountId, action: (String, String)) {
+            if self.org_grants.take(&(account, action.0.clone(), action.1.clone())).is_none() {
+                return;
+            }
+            if let Some(mut auth_instance) = self.auth() {
+                auth_instance.revoke_permission(account, action.0, action.1);
+            }
+        }
+
+        fn tier_actions() -> alloc::vec::Vec<(String, String)> {
+            let mut actions:alloc::vec::Vec<(String, String)> = alloc::vec::Vec::new();
+            actions.push((String::from("vote"), String::from("new")));
+            actions.push((String::from("vote"), String::from("vote")));
+            actions
+        }
+
+        fn role_actions(&self) -> alloc::vec::Vec<(String, String)> {
+            let mut actions:alloc::vec::Vec<(String, String)> = alloc::vec::Vec::new();
+            for role in self.roles.values() {
+                for action in &role.actions {
+                    if !actions.contains(action) {
+                        actions.push(action.clone());
+                    }
+                }
+            }
+            actions
+        }
+
+        /// Repairs drift between the org and auth: every current holder of the tier
+        /// actions and every org account is synced. By moderators or owner, returns
+        /// the number of accounts synced.
+        #[ink(message)]
+        pub fn repair_permissions(&mut self) -> u32 {
+            self.ensure_not_paused();
+            let caller = self.env().caller();
+            if !self.check_authority(caller) {
+                return 0;
+            }
+            let auth_instance = match self.auth() {
+                None => return 0,
+                Some(auth_instance) => auth_instance,
+            };
+            let mut accounts:alloc::vec::Vec<AccountId> = alloc::vec::Vec::new();
+            for (contract_name, function_name) in Self::tier_actions() {
+                for holder in auth_instance.get_action_holders(contract_name, function_name) {
+                    if !accounts.contains(&holder) {
+                        accounts.push(holder);
+                    }
+                }
+            }
+            for account in self.get_dao_members_list().into_iter()
+                .chain(self.get_dao_moderator_list().into_iter()) {
+                if !accounts.contains(&account) {
+                    accounts.push(account);
+                }
+            }
+            if !accounts.contains(&self.owner) {
+                accounts.push(self.owner);
+            }
+            for account in &accounts {
+                self.sync_account(*account);
+            }
+            accounts.len() as u32
+        }
+
+        #[ink(message)]
+        pub fn get_roles(&self) -> alloc::vec::Vec<Role> {
+            let mut v:alloc::vec::Vec<Role> = alloc::vec::Vec::new();
+            for role in self.roles.values() {
+                v.push(role.clone())
+            }
+            v
+        }
+
+        #[ink(message)]
+        pub fn get_role_members(&self, name: String) -> alloc::vec::Vec<AccountId> {
+            let mut v:alloc::vec::Vec<AccountId> = alloc::vec::Vec::new();
+            for (role, account) in self.role_members.keys() {
+                if *role == name {
+                    v.push(*account)
+                }
+            }
+            v
+        }
+
+        #[ink(message)]
+        pub fn get_account_roles(&self, account: AccountId) -> alloc::vec::Vec<String> {
+            let mut v:alloc::vec::Vec<String> = alloc::vec::Vec::new();
+            for (role, holder) in self.role_members.keys() {
+                if *holder == account {
+                    v.push(role.clone())
+                }
+            }
+            v
+        }
+
+    }
 
         
     
@@ -582,6 +1908,20 @@ mod org {
         };
         use ink_lang as ink;
 
+        const DEFAULT_CALLEE_HASH: [u8; 32] = [0x07; 32];
+        const DEFAULT_ENDOWMENT: Balance = 1_000_000;
+        const DEFAULT_GAS_LIMIT: Balance = 1_000_000;
+
+        fn set_next_caller(caller: AccountId) {
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller,
+                AccountId::from(DEFAULT_CALLEE_HASH),
+                DEFAULT_ENDOWMENT,
+                DEFAULT_GAS_LIMIT,
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])),
+            )
+        }
+
         #[ink::test]
         fn new_org_works() {
             let accounts =
@@ -667,12 +2007,188 @@ mod org {
             org_manager.add_dao_member(eve_name,accounts.eve);
             let mut members = org_manager.get_dao_members_list();
             assert_eq!(members.len(), 2);
-            org_manager.resign_member(accounts.bob);
+            set_next_caller(accounts.bob);
+            org_manager.resign_member();
             members = org_manager.get_dao_members_list();
             assert_eq!(members.len(), 1);
-            org_manager.resign_member(accounts.eve);
+            set_next_caller(accounts.eve);
+            org_manager.resign_member();
             members = org_manager.get_dao_members_list();
             assert_eq!(members.len(), 0);
         }
+
+        #[ink::test]
+        fn resign_only_affects_caller() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked, so nothing calls into auth
+            let mut org_manager = OrgManager::new(accounts.alice,1, AccountId::default());
+            org_manager.add_dao_member(String::from("bob"),accounts.bob);
+            // alice is not a member, resigning as alice leaves bob alone
+            assert!(!org_manager.resign_member());
+            assert_eq!(org_manager.get_dao_members_list().len(), 1);
+        }
+
+        #[ink::test]
+        fn application_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked, so nothing calls into auth
+            let mut org_manager = OrgManager::new(accounts.alice,1, AccountId::default());
+            set_next_caller(accounts.bob);
+            assert!(org_manager.apply_member(String::from("bob"), Hash::from([0x01; 32])));
+            assert!(!org_manager.apply_member(String::from("bob"), Hash::from([0x01; 32])));
+            assert!(org_manager.withdraw_application());
+            assert!(org_manager.apply_member(String::from("bob"), Hash::from([0x02; 32])));
+            ink_env::test::pop_execution_context();
+
+            assert!(org_manager.reject_member(accounts.bob, String::from("no statement")));
+            assert_eq!(org_manager.get_dao_apply_member_detail_list().len(), 0);
+            let history = org_manager.get_application_history(accounts.bob);
+            assert_eq!(history.len(), 2);
+            assert_eq!(history[0].status, 3);
+            assert_eq!(history[1].status, 2);
+            assert_eq!(history[1].reason, Some(String::from("no statement")));
+        }
+
+        #[ink::test]
+        fn admission_policy_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked, so nothing calls into auth
+            let mut org_manager = OrgManager::new(accounts.alice,1, AccountId::default());
+            // needs the vote contract first
+            assert!(!org_manager.set_admission_policy(100, 1, 1, 0));
+            set_next_caller(accounts.bob);
+            assert!(!org_manager.set_vote_contract(accounts.bob));
+            ink_env::test::pop_execution_context();
+            assert!(org_manager.set_vote_contract(accounts.charlie));
+            assert!(org_manager.set_admission_policy(100, 1, 1, 0));
+            // forfeited deposits go to the vault
+            assert!(!org_manager.set_admission_policy(100, 1, 1, 10));
+            assert!(org_manager.set_vault_contract(accounts.django));
+            assert!(org_manager.set_admission_policy(100, 1, 1, 10));
+            // nothing to close without a pending application
+            assert!(!org_manager.close_failed_application(accounts.bob));
+            assert_eq!(org_manager.get_admission_policy().unwrap().vote_time, 100);
+            // moderators can't approve while admission is by vote
+            assert!(!org_manager.approve_member(String::from("bob"), accounts.bob));
+            assert!(org_manager.clear_admission_policy());
+            assert_eq!(org_manager.get_admission_policy(), None);
+        }
+
+        #[ink::test]
+        fn token_gate_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked, so nothing calls into auth
+            let mut org_manager = OrgManager::new(accounts.alice,1, AccountId::default());
+            assert!(!org_manager.claim_membership(String::from("alice")));
+            assert!(!org_manager.prune(accounts.bob));
+            assert!(!org_manager.set_token_gate(accounts.django, 0));
+            set_next_caller(accounts.bob);
+            assert!(!org_manager.set_token_gate(accounts.django, 10));
+            ink_env::test::pop_execution_context();
+            assert!(org_manager.set_token_gate(accounts.django, 10));
+            assert_eq!(org_manager.get_token_gate().unwrap().threshold, 10);
+            // only members who claimed can be pruned
+            assert!(!org_manager.prune(accounts.bob));
+            assert!(org_manager.clear_token_gate());
+            assert_eq!(org_manager.get_token_gate(), None);
+        }
+
+        #[ink::test]
+        fn membership_terms_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked, so nothing calls into auth
+            let mut org_manager = OrgManager::new(accounts.alice,1, AccountId::default());
+            org_manager.add_dao_member(String::from("bob"),accounts.bob);
+            // dues are paid into the vault
+            assert!(!org_manager.set_membership_terms(None, 10, 100, 10));
+            assert!(org_manager.set_vault_contract(accounts.django));
+            assert!(org_manager.set_membership_terms(None, 10, 100, 10));
+            org_manager.add_dao_member(String::from("charlie"),accounts.charlie);
+            // bob joined before the terms and doesn't expire
+            assert_eq!(org_manager.get_membership_expiry(accounts.bob), None);
+            let expiry = org_manager.get_membership_expiry(accounts.charlie).unwrap();
+            assert_eq!(org_manager.get_expiring_members(100), vec![(accounts.charlie, expiry)]);
+            assert_eq!(org_manager.get_expiring_members(u64::MAX), vec![(accounts.charlie, expiry)]);
+            // approved, admitted, claimed and invited members expire as well
+            assert!(org_manager.add_dao_member_private(String::from("eve"), accounts.eve));
+            assert_eq!(org_manager.get_membership_expiry(accounts.eve), Some(expiry));
+            assert_eq!(org_manager.get_lapsed_members().len(), 0);
+            assert!(!org_manager.is_lapsed_member(accounts.charlie));
+            assert!(!org_manager.revoke_lapsed(accounts.charlie));
+            assert!(org_manager.clear_membership_terms());
+            assert_eq!(org_manager.get_membership_expiry(accounts.charlie), None);
+        }
+
+        #[ink::test]
+        fn invite_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked, so joining doesn't call into auth
+            let mut org_manager = OrgManager::new(accounts.alice,1, AccountId::default());
+            let secret = vec![0x01u8, 0x02, 0x03];
+            let mut output = <Blake2x256 as ink_env::hash::HashOutput>::Type::default();
+            ink_env::hash_bytes::<Blake2x256>(&secret, &mut output);
+            let commitment = Hash::from(output);
+            let expire_time = ink_env::block_timestamp::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get block timestamp") + 100;
+            assert!(!org_manager.create_invite(commitment, 0, expire_time));
+            assert!(org_manager.create_invite(commitment, 1, expire_time));
+            assert!(!org_manager.create_invite(commitment, 1, expire_time));
+
+            // bob commits first and reveals in a later block
+            let bob_claim = org_manager.invite_claim_hash(secret.clone(), accounts.bob);
+            set_next_caller(accounts.bob);
+            assert!(org_manager.commit_invite_claim(bob_claim));
+            assert!(!org_manager.redeem_invite(String::from("bob"), secret.clone()));
+            ink_env::test::pop_execution_context();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().expect("Cannot advance block");
+
+            // charlie saw the secret but never committed a claim
+            set_next_caller(accounts.charlie);
+            assert!(!org_manager.redeem_invite(String::from("charlie"), secret.clone()));
+            ink_env::test::pop_execution_context();
+
+            set_next_caller(accounts.bob);
+            assert!(!org_manager.redeem_invite(String::from("bob"), vec![0x09u8]));
+            assert!(org_manager.redeem_invite(String::from("bob"), secret.clone()));
+            ink_env::test::pop_execution_context();
+            assert_eq!(org_manager.get_dao_members_list(), vec![accounts.bob]);
+            assert_eq!(org_manager.get_invite(commitment).unwrap().uses, 1);
+            assert_eq!(org_manager.get_invite_uses(commitment).len(), 1);
+
+            // used up, even for a committed claim
+            let charlie_claim = org_manager.invite_claim_hash(secret.clone(), accounts.charlie);
+            set_next_caller(accounts.charlie);
+            assert!(org_manager.commit_invite_claim(charlie_claim));
+            ink_env::test::pop_execution_context();
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().expect("Cannot advance block");
+            set_next_caller(accounts.charlie);
+            assert!(!org_manager.redeem_invite(String::from("charlie"), secret));
+            ink_env::test::pop_execution_context();
+        }
+
+        #[ink::test]
+        fn create_role_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            let mut org_manager = OrgManager::new(accounts.alice,1, accounts.alice);
+            let actions = vec![(String::from("vault"), String::from("budget"))];
+            assert!(org_manager.create_role_private(String::from("treasurer"), actions.clone()));
+            assert!(!org_manager.create_role_private(String::from("treasurer"), actions));
+            assert_eq!(org_manager.get_roles().len(), 1);
+            assert_eq!(org_manager.get_role_members(String::from("treasurer")).len(), 0);
+        }
     }
 }
@@ -16,6 +16,7 @@ mod red_packet_manager {
         collections::HashMap as StorageHashMap,
     };
     use ink_prelude::collections::BTreeMap;
+    use ink_prelude::vec::Vec;
     use erc20::Erc20;
 
     #[derive(scale::Encode, scale::Decode, Clone, SpreadLayout, PackedLayout)]
@@ -39,12 +40,12 @@ mod red_packet_manager {
         // red packet password
         password: Hash,
         // red packet remaining tokens
-        remaining_tokens: u64,
+        remaining_tokens: Balance,
         // red packet start time
         start_time: u64,
         end_time: u64,
         // claim tokens map by address
-        claim_list: BTreeMap<AccountId, u64>,
+        claim_list: BTreeMap<AccountId, Balance>,
         // is refund by creator
         is_refund: bool,
     }
@@ -85,7 +86,7 @@ mod red_packet_manager {
 
         #[ink(message)]
         pub fn create_red_packet(&mut self, token_type: u8, if_random: u8, total_number: u64,
-                                 end_time: u64, token_addr: AccountId, total_tokens: u64, password: Hash) -> u64 {
+                                 end_time: u64, token_addr: AccountId, total_tokens: Balance, password: Hash) -> u64 {
             let from = self.env().caller();
             // assert!(self.index + 1 > self.index, "cannot create red packet more");
             // assert!(token_type == 0 || token_type == 1, "wrong token type");
@@ -107,7 +108,7 @@ mod red_packet_manager {
                 // token_addr = AccountId::from([0x00; 32]);
             } else {
                 let mut token :Erc20 = ink_env::call::FromAccountId::from_account_id(token_addr);
-                assert!(token.transfer_from(from, contract_addr, total_tokens), "transfer_from token failed");
+                assert!(token.transfer_from(from, contract_addr, total_tokens, Vec::new()).is_ok(), "transfer_from token failed");
             }
             self.red_packet_map.insert(self.index, RedPacket {
                 id: self.index,
@@ -132,17 +133,30 @@ mod red_packet_manager {
             self.index
         }
 
+        /// Creates an erc20 red packet, the creator's signed permit approves this contract
+        /// for `total_tokens` in the same call.
         #[ink(message)]
-        pub fn transfer_token(&mut self, token_addr: AccountId, total_tokens: u64) -> bool {
+        pub fn create_red_packet_with_permit(&mut self, if_random: u8, total_number: u64, end_time: u64,
+                                             token_addr: AccountId, total_tokens: Balance, password: Hash,
+                                             deadline: u64, signature: [u8; 65]) -> u64 {
             let from = self.env().caller();
             let contract_addr = self.env().account_id();
             let mut token :Erc20 = ink_env::call::FromAccountId::from_account_id(token_addr);
-            assert!(token.transfer_from(from, contract_addr, total_tokens), "transfer_from token failed");
+            assert!(token.permit(from, contract_addr, total_tokens, deadline, signature).is_ok(), "permit failed");
+            self.create_red_packet(1, if_random, total_number, end_time, token_addr, total_tokens, password)
+        }
+
+        #[ink(message)]
+        pub fn transfer_token(&mut self, token_addr: AccountId, total_tokens: Balance) -> bool {
+            let from = self.env().caller();
+            let contract_addr = self.env().account_id();
+            let mut token :Erc20 = ink_env::call::FromAccountId::from_account_id(token_addr);
+            assert!(token.transfer_from(from, contract_addr, total_tokens, Vec::new()).is_ok(), "transfer_from token failed");
             true
         }
 
         #[ink(message)]
-        pub fn allowance_token(&mut self, token_addr: AccountId) -> u64 {
+        pub fn allowance_token(&mut self, token_addr: AccountId) -> Balance {
             let from = self.env().caller();
             let contract_addr = self.env().account_id();
             let mut token :Erc20 = ink_env::call::FromAccountId::from_account_id(token_addr);
@@ -172,11 +186,11 @@ mod red_packet_manager {
                 claimed_tokens = remaining_tokens;
             } else if red_packet.if_random == 0 {
                 // if no random, average tokens
-                claimed_tokens = remaining_tokens / (total_number - claimed_number);
+                claimed_tokens = remaining_tokens / Balance::from(total_number - claimed_number);
             } else if red_packet.if_random == 1 {
                 // if random, random 1 - (remaining_tokens * 2 / (total_number - claimed_number) - 1)
                 // TODO cannot use mul?
-                claimed_tokens = (self.seed + red_packet.id + cur_time + 1) % ((remaining_tokens + remaining_tokens) / (total_number - claimed_number))
+                claimed_tokens = Balance::from(self.seed + red_packet.id + cur_time + 1) % ((remaining_tokens + remaining_tokens) / Balance::from(total_number - claimed_number))
             }
 
             // modify red packet
@@ -189,7 +203,7 @@ mod red_packet_manager {
                 // TODO claim unit
             } else if red_packet.token_type == 1 {
                 let mut token :Erc20 = ink_env::call::FromAccountId::from_account_id(token_addr);
-                assert!(token.transfer(recipient, claimed_tokens), "transfer token failed");
+                assert!(token.transfer(recipient, claimed_tokens, Vec::new()).is_ok(), "transfer token failed");
             }
 
             // TODO event
@@ -215,7 +229,7 @@ mod red_packet_manager {
                 // TODO claim unit
             } else if red_packet.token_type == 1 {
                 let mut token :Erc20 = ink_env::call::FromAccountId::from_account_id(token_addr);
-                assert!(token.transfer(from, remaining_tokens), "transfer token failed");
+                assert!(token.transfer(from, remaining_tokens, Vec::new()).is_ok(), "transfer token failed");
             }
             red_packet.is_refund = true;
             // TODO event
@@ -4,22 +4,48 @@ extern crate alloc;
 use ink_lang as ink;
 //use ink_prelude::vec::Vec;
 pub use self::vault::VaultManager;
+pub use self::vault::Asset;
+pub use self::vault::BudgetSpender;
+pub use self::vault::ReimbursementRequest;
+pub use self::vault::RequestPolicy;
+pub use self::vault::{NATIVE_TOKEN_NAME, NATIVE_TOKEN_SYMBOL};
 
 #[ink::contract]
 mod vault {
 
     use alloc::string::String;
+    use core::convert::TryFrom;
 
     use ink_storage::{
         collections::HashMap as StorageHashMap,
         traits::{PackedLayout, SpreadLayout},
 
     };
+    use ink_env::call::{build_call, utils::ReturnType, ExecutionInput, Selector};
 
-    use erc20::Erc20;
+    use erc20::{Erc20, PSP22Error};
     use org::OrgManager;
     use auth::Auth;
 
+    /// An asset held by the vault: the chain's native token or an erc20 contract.
+    #[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub enum Asset {
+        Native,
+        Erc20(AccountId),
+    }
+
+    impl Default for Asset {
+        fn default() -> Self {
+            Asset::Native
+        }
+    }
+
     #[derive(
     Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout,Default
     )]
@@ -29,12 +55,121 @@ mod vault {
     )]
     pub struct Transfer {
         transfer_id:u64,
-        transfer_direction:u64,// 1: out 2 : in
+        transfer_direction:u64,// 1: out 2 : in 3: unattributed inflow 4: unattributed outflow
+        asset: Asset,
         token_name: String,
         from_address:AccountId,
         to_address:AccountId,
-        value: u64,
+        value: Balance,
         transfer_time:u64,
+        memo: Option<String>,
+        // the vote which triggered an outgoing transfer
+        vote_id: Option<u64>,
+    }
+
+    /// Who may spend a budget: a single account, or any holder of an auth action
+    /// given as (contract_name, function_name).
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub enum BudgetSpender {
+        Account(AccountId),
+        Action(String, String),
+    }
+
+    /// A named part of the vault balance earmarked for a working group.
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct Budget {
+        budget_id: u64,
+        name: String,
+        asset: Asset,
+        spender: BudgetSpender,
+        allocated: Balance,
+        spent: Balance,
+    }
+
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout, Default
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct BudgetEntry {
+        entry_id: u64,
+        budget_id: u64,
+        entry_type: u8,// 1: top up 2: reclaim 3: spend
+        value: Balance,
+        to_address: Option<AccountId>,
+        transfer_id: Option<u64>,
+        entry_time: u64,
+    }
+
+    /// A member's reimbursement request, paid on moderator approval up to the
+    /// asset's ceiling or after a passed vote above it.
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct ReimbursementRequest {
+        request_id: u64,
+        applicant: AccountId,
+        asset: Asset,
+        value: Balance,
+        evidence: Hash,
+        status: u8,// 0: pending 1: paid 2: rejected 3: escalated to a vote
+        reviewer: Option<AccountId>,
+        vote_id: Option<u64>,
+        transfer_id: Option<u64>,
+        create_time: u64,
+    }
+
+    /// How reimbursement requests in one asset are handled.
+    #[derive(
+    Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout, Default
+    )]
+    #[cfg_attr(
+    feature = "std",
+    derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
+    )]
+    pub struct RequestPolicy {
+        // largest amount moderators can approve on their own
+        ceiling: Balance,
+        // parameters of the vote opened for larger requests
+        vote_time: u64,
+        support_require_num: u64,
+        min_require_num: u64,
+    }
+
+    /// Summary of one asset's vault activity in a time window.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct TreasuryReport {
+        asset: Asset,
+        start_time: u64,
+        end_time: u64,
+        opening_balance: Balance,
+        inflows: Balance,
+        outflows: Balance,
+        closing_balance: Balance,
+        net_change: i128,
+        // counterparties by transferred volume in the window, largest first
+        top_counterparties: ink_prelude::vec::Vec<(AccountId, Balance)>,
+        // votes behind the outflows in the window
+        outflow_vote_ids: ink_prelude::vec::Vec<u64>,
     }
 
     // Token info for query purpose.
@@ -46,10 +181,10 @@ mod vault {
         derive(::scale_info::TypeInfo, ::ink_storage::traits::StorageLayout)
         )]
     pub struct TokenInfo {
-        erc20: AccountId,
+        asset: Asset,
         symbol: String,
         name: String,
-        balance: u64,
+        balance: Balance,
     }
 
     #[ink(storage)]
@@ -57,10 +192,26 @@ mod vault {
 
         tokens: StorageHashMap<AccountId, AccountId>,
         visible_tokens: StorageHashMap<AccountId, AccountId>,
+        // token name cached when the token is added, so the receiver hook doesn't call back into the token
+        token_names: StorageHashMap<AccountId, String>,
         transfer_history:StorageHashMap<u64,Transfer>,
+        depositor_totals:StorageHashMap<(Asset,AccountId),Balance>,
+        budgets:StorageHashMap<u64,Budget>,
+        budget_count:u64,
+        budget_entries:StorageHashMap<u64,BudgetEntry>,
+        earmarked:StorageHashMap<Asset,Balance>,
+        // balances according to the transfer history
+        ledger:StorageHashMap<Asset,Balance>,
         org_contract_address:AccountId,
         vault_contract_address:AccountId,
         auth_contract_address:AccountId,
+        // the contract which instantiated the vault, usually the dao manager
+        creator:AccountId,
+        dao_token:Option<AccountId>,
+        vote_contract_address:Option<AccountId>,
+        requests:StorageHashMap<u64,ReimbursementRequest>,
+        request_count:u64,
+        request_policies:StorageHashMap<Asset,RequestPolicy>,
     }
 
     /// Errors that can occur upon calling this contract.
@@ -70,6 +221,14 @@ mod vault {
         InvalidTransferRecord,
     }
 
+    pub const NATIVE_TOKEN_SYMBOL: &str = "gov";
+    pub const NATIVE_TOKEN_NAME: &str = "subDAO";
+
+    // `VoteManager::has_pending_approval`, called by selector since vote_manager depends on this crate.
+    const HAS_PENDING_APPROVAL_SELECTOR: [u8; 4] = [0x2d, 0x8c, 0x4e, 0x11];
+    // `VoteManager::new_request_vote`
+    const NEW_REQUEST_VOTE_SELECTOR: [u8; 4] = [0x5a, 0x3c, 0x9e, 0x27];
+
 
     #[ink(event)]
     pub struct AddVaultTokenEvent {
@@ -96,7 +255,7 @@ mod vault {
         token_address:AccountId,
 
         #[ink(topic)]
-        balance:u64,
+        balance:Balance,
     }
 
     #[ink(event)]
@@ -108,7 +267,7 @@ mod vault {
         from_address:AccountId,
 
         #[ink(topic)]
-        value:u64,
+        value:Balance,
     }
 
 
@@ -121,9 +280,96 @@ mod vault {
         to_address:AccountId,
 
         #[ink(topic)]
-        value:u64,
+        value:Balance,
+    }
+
+    #[ink(event)]
+    pub struct CreateBudgetEvent {
+        #[ink(topic)]
+        budget_id:u64,
+
+        name:String,
+
+        value:Balance,
+    }
+
+    #[ink(event)]
+    pub struct TopUpBudgetEvent {
+        #[ink(topic)]
+        budget_id:u64,
+
+        value:Balance,
+    }
+
+    #[ink(event)]
+    pub struct ReclaimBudgetEvent {
+        #[ink(topic)]
+        budget_id:u64,
+
+        value:Balance,
+    }
+
+    #[ink(event)]
+    pub struct SpendBudgetEvent {
+        #[ink(topic)]
+        budget_id:u64,
+
+        #[ink(topic)]
+        spender:AccountId,
+
+        to_address:AccountId,
+
+        value:Balance,
+    }
+
+    #[ink(event)]
+    pub struct ReconcileEvent {
+        #[ink(topic)]
+        token_name:String,
+
+        inflow:bool,
+
+        value:Balance,
+    }
+
+    #[ink(event)]
+    pub struct FileRequestEvent {
+        #[ink(topic)]
+        request_id:u64,
+
+        #[ink(topic)]
+        applicant:AccountId,
+
+        value:Balance,
+    }
+
+    #[ink(event)]
+    pub struct ReviewRequestEvent {
+        #[ink(topic)]
+        request_id:u64,
+
+        #[ink(topic)]
+        reviewer:AccountId,
+
+        status:u8,
+    }
+
+    #[ink(event)]
+    pub struct EscalateRequestEvent {
+        #[ink(topic)]
+        request_id:u64,
+
+        #[ink(topic)]
+        vote_id:u64,
     }
 
+    #[ink(event)]
+    pub struct RagequitEvent {
+        #[ink(topic)]
+        member:AccountId,
+
+        shares:Balance,
+    }
 
 
 
@@ -140,13 +386,49 @@ mod vault {
                 auth_contract_address:auth_contract_address,
                 tokens: StorageHashMap::default(),
                 visible_tokens: StorageHashMap::default(),
+                token_names: StorageHashMap::default(),
                 transfer_history: StorageHashMap::default(),
+                depositor_totals: StorageHashMap::default(),
+                budgets: StorageHashMap::default(),
+                budget_count: 0,
+                budget_entries: StorageHashMap::default(),
+                earmarked: StorageHashMap::default(),
+                ledger: StorageHashMap::default(),
                 vault_contract_address: vault_contract_address,
+                creator: Self::env().caller(),
+                dao_token: None,
+                vote_contract_address: None,
+                requests: StorageHashMap::default(),
+                request_count: 0,
+                request_policies: StorageHashMap::default(),
 
 
             }
         }
 
+        #[ink(message)]
+        pub fn set_dao_token(&mut self, dao_token: AccountId) -> bool {
+            if self.env().caller() != self.creator {
+                return false;
+            }
+            self.dao_token = Some(dao_token);
+            true
+        }
+
+        #[ink(message)]
+        pub fn get_dao_token(&self) -> Option<AccountId> {
+            self.dao_token
+        }
+
+        #[ink(message)]
+        pub fn set_vote_contract(&mut self, vote_contract_address: AccountId) -> bool {
+            if self.env().caller() != self.creator {
+                return false;
+            }
+            self.vote_contract_address = Some(vote_contract_address);
+            true
+        }
+
 
 
         pub fn get_erc20_by_address(&self, address:AccountId) -> Erc20 {
@@ -171,17 +453,20 @@ mod vault {
         #[ink(message)]
         pub fn add_vault_token(&mut self,erc_20_address:AccountId) -> bool  {
 
-            let _caller = self.env().caller();
+            let caller = self.env().caller();
+
+            let is_permission = self.has_vault_permission(caller, "add_vault_token");
 
-            let  _auth = self.get_auth_by_address(self.auth_contract_address);
-            
-            // let is_permission = auth.has_permission(caller,String::from("vault"),String::from("add_vault_token"));
-            let is_permission = true;
-            
             if is_permission == false {
                 return false;
             }
 
+            let token_name = self.get_erc20_by_address(erc_20_address).token_name().unwrap_or_default();
+            self.token_names.insert(erc_20_address, token_name);
+            self.add_vault_token_private(erc_20_address)
+        }
+
+        fn add_vault_token_private(&mut self,erc_20_address:AccountId) -> bool  {
             match self.tokens.insert(
                                      erc_20_address,self.vault_contract_address
             ) {
@@ -205,19 +490,18 @@ mod vault {
         #[ink(message)]
         pub fn remove_vault_token(&mut self,erc_20_address: AccountId) -> bool  {
 
-            let _caller = self.env().caller();
-
-            let _auth = self.get_auth_by_address(self.auth_contract_address);
+            let caller = self.env().caller();
 
-            //let is_permission = auth.has_permission(caller,String::from("vault"),String::from("remove_vault_token"));
-            let is_permission = true;
+            let is_permission = self.has_vault_permission(caller, "remove_vault_token");
 
             if is_permission == false {
                 return false;
             }
 
+            self.remove_vault_token_private(erc_20_address)
+        }
 
-
+        fn remove_vault_token_private(&mut self,erc_20_address: AccountId) -> bool  {
             match self.visible_tokens.take(&erc_20_address) {
                 None => { false}
                 Some(_) => {
@@ -232,30 +516,27 @@ mod vault {
 
 
         #[ink(message)]
-        pub fn get_token_list(&self) -> ink_prelude::vec::Vec<AccountId> {
-            self.visible_tokens.keys();
-            let mut v:ink_prelude::vec::Vec<AccountId> = ink_prelude::vec::Vec::new();
+        pub fn get_token_list(&self) -> ink_prelude::vec::Vec<Asset> {
+            let mut v:ink_prelude::vec::Vec<Asset> = ink_prelude::vec::Vec::new();
             for key in self.visible_tokens.keys() {
-                v.push(*key)
+                v.push(Asset::Erc20(*key))
             }
-            v.push(AccountId::from([0xee; 32]));
+            v.push(Asset::Native);
             v
         }
 
 
 
         #[ink(message)]
-        pub fn get_balance_of(&self,erc_20_address: AccountId) -> u64 {
+        pub fn get_balance_of(&self, asset: Asset) -> Balance {
 
-            if erc_20_address == AccountId::from([0xee; 32]) {
-                let value = self.env().balance() as u64;
-                return value;
-            }
+            let erc_20_address = match asset {
+                Asset::Native => return self.env().balance(),
+                Asset::Erc20(erc_20_address) => erc_20_address,
+            };
             if self.tokens.contains_key(&erc_20_address) {
 
-               // let mut erc_20 = self.get_erc20_by_address(*erc_20_address.unwrap());
                 let  erc_20 = self.get_erc20_by_address(erc_20_address);
-                //let token_name = (&erc_20).name();
                 let balanceof = erc_20.balance_of(self.vault_contract_address);
 
 
@@ -273,75 +554,60 @@ mod vault {
         #[ink(message)]
         pub fn get_balance(&self) -> ink_prelude::vec::Vec<TokenInfo> {
 
-            self.visible_tokens.keys();
             let mut v:ink_prelude::vec::Vec<TokenInfo> = ink_prelude::vec::Vec::new();
             for address in self.visible_tokens.keys() {
 
                 let  erc20_instance: Erc20 = ink_env::call::FromAccountId::from_account_id(*address);
                 v.push(TokenInfo{
-                    erc20: *address,
-                    symbol: erc20_instance.symbol(),
-                    name: erc20_instance.name(),
+                    asset: Asset::Erc20(*address),
+                    symbol: erc20_instance.token_symbol().unwrap_or_default(),
+                    name: erc20_instance.token_name().unwrap_or_default(),
                     balance: erc20_instance.balance_of(self.vault_contract_address),
                 })
             }
-            let value = self.env().balance() as u64;
             v.push(TokenInfo{
-                erc20: AccountId::from([0xee; 32]),
-                symbol: String::from("gov"),
-                name: String::from("subDAO"),
-                balance: value,
+                asset: Asset::Native,
+                symbol: String::from(NATIVE_TOKEN_SYMBOL),
+                name: String::from(NATIVE_TOKEN_NAME),
+                balance: self.env().balance(),
             });
             v
         }
 
 
-        #[ink(message)]
-        pub fn deposit(&mut self, erc_20_address:AccountId, from_address:AccountId,value:u64) -> bool {
+        /// Deposits are always attributed to the caller. Native deposits must carry
+        /// exactly `value` as transferred balance, erc20 deposits must not carry any
+        /// and pull `value` from the caller's allowance to the vault. Fixed selector,
+        /// the org pays membership dues through it.
+        #[ink(message, payable, selector = "0x3b7d2a64")]
+        pub fn deposit(&mut self, asset: Asset, value:Balance, memo: Option<String>) -> bool {
 
-            if erc_20_address == AccountId::from([0xee; 32]) {
-                return self.deposit_native_token();
-            }
+            let from_address = self.env().caller();
+            let transferred = self.env().transferred_balance();
+            let erc_20_address = match asset {
+                Asset::Native => {
+                    assert!(transferred == value, "transferred value mismatch");
+                    return self.deposit_native_token(value, memo);
+                }
+                Asset::Erc20(erc_20_address) => erc_20_address,
+            };
+            assert!(transferred == 0, "native token sent with erc20 deposit");
             let to_address = self.vault_contract_address;
 
             if self.tokens.contains_key(&erc_20_address) {
 
-                // let  balanceof =  self.get_balance_of(erc_20_address);
-
-
-                //let mut erc_20 = self.get_erc20_by_address(*erc_20_address.unwrap());
                 let mut erc_20 = self.get_erc20_by_address(erc_20_address);
 
-                let token_name = (&erc_20).name();
+                let token_name = (&erc_20).token_name().unwrap_or_default();
+
 
-                
-                let transfer_result = erc_20.transfer_from(from_address,to_address, value);
+                let transfer_result = erc_20.transfer_from(from_address,to_address, value, ink_prelude::vec::Vec::new());
 
-                if transfer_result == false {
+                if transfer_result.is_err() {
                     return false;
                 }
 
-                let transfer_id:u64 = (self.transfer_history.len()+1).into();
-
-                let transfer_time: u64 = self.env().block_timestamp();
-
-
-                self.transfer_history.insert(transfer_id,
-                                             Transfer{
-
-                                                 transfer_direction:2,// 1: out 2: in
-                                                 token_name:token_name.clone(),
-                                                 transfer_id:transfer_id,
-                                                 from_address:from_address,
-                                                 to_address:to_address,
-                                                 value,
-                                                 transfer_time});
-
-
-                self.env().emit_event(DepositTokenEvent{
-                    token_name: token_name.clone(),
-                    from_address:from_address,
-                    value:value});
+                self.record_deposit(asset, token_name, from_address, value, memo);
                 true
 
             } else{
@@ -349,155 +615,723 @@ mod vault {
             }
         }
 
-        pub fn deposit_native_token(&mut self) -> bool {
+        fn deposit_native_token(&mut self, value: Balance, memo: Option<String>) -> bool {
             let from_address = self.env().caller();
-            let value = self.env().transferred_balance();
             assert!(value > 0, "value is 0");
-            let to_address = self.vault_contract_address;
 
-            let transfer_id:u64 = (self.transfer_history.len()+1).into();
-            let transfer_time: u64 = self.env().block_timestamp();
+            self.record_deposit(Asset::Native, String::from(NATIVE_TOKEN_NAME), from_address, value, memo);
+            true
+        }
 
-            let value2 = value as u64;
-            self.transfer_history.insert(transfer_id,
-                                         Transfer{
-                                             transfer_direction:2,// 1: out 2: in
-                                             token_name:String::from("subDAO"),
-                                             transfer_id:transfer_id,
-                                             from_address:from_address,
-                                             to_address:to_address,
-                                             value: value2,
-                                             transfer_time});
+        /// Erc20 deposit in one call, the caller's signed permit approves the vault first.
+        #[ink(message)]
+        pub fn deposit_with_permit(&mut self, erc_20_address: AccountId, value: Balance, memo: Option<String>,
+                                   deadline: u64, signature: [u8; 65]) -> bool {
+            let caller = self.env().caller();
+            if !self.tokens.contains_key(&erc_20_address) {
+                return false;
+            }
+            let mut erc_20 = self.get_erc20_by_address(erc_20_address);
+            if erc_20.permit(caller, self.vault_contract_address, value, deadline, signature).is_err() {
+                return false;
+            }
+            self.deposit(Asset::Erc20(erc_20_address), value, memo)
+        }
 
+        /// Called by a token after `transfer_and_call`, records the tokens as a deposit
+        /// from `from`. Non-empty utf-8 `data` is kept as the memo.
+        #[ink(message, selector = "0x5fc19b73")]
+        pub fn on_token_received(&mut self, _operator: AccountId, from: AccountId, value: Balance,
+                                 data: ink_prelude::vec::Vec<u8>) -> Result<(), PSP22Error> {
+            let erc_20_address = self.env().caller();
+            if !self.tokens.contains_key(&erc_20_address) {
+                return Err(PSP22Error::SafeTransferCheckFailed(String::from("token not supported by the vault")));
+            }
+            let memo = if data.is_empty() {
+                None
+            } else {
+                String::from_utf8(data).ok()
+            };
+            // the token is still executing `transfer_and_call`, don't call back into it
+            let token_name = self.token_names.get(&erc_20_address).cloned().unwrap_or_default();
+            self.record_deposit(Asset::Erc20(erc_20_address), token_name, from, value, memo);
+            Ok(())
+        }
+
+        fn record_deposit(&mut self, asset: Asset, token_name: String, from_address: AccountId,
+                          value: Balance, memo: Option<String>) {
+            let to_address = self.vault_contract_address;
+            self.record_transfer(2, asset, token_name.clone(), from_address, to_address, value, memo, None);
+
+            let total = self.depositor_totals.entry((asset, from_address)).or_insert(0);
+            *total = total.saturating_add(value);
 
             self.env().emit_event(DepositTokenEvent{
-                token_name: String::from("subDAO"),
-                from_address: from_address,
-                value: value2
+                token_name,
+                from_address,
+                value,
             });
-            true
         }
 
 
 
+        /// `vote_id` is the proposal which triggered the withdrawal, if any.
         #[ink(message)]
-        pub fn withdraw(&mut self,erc_20_address:AccountId,to_address:AccountId,value:u64) -> bool {
+        pub fn withdraw(&mut self, asset: Asset, to_address:AccountId, value:Balance, vote_id: Option<u64>) -> bool {
+
+            let caller = self.env().caller();
+
+            // only holders of `vault.withdraw` (the vote manager) can move funds and name the vote
+            let is_permission = self.has_vault_permission(caller, "withdraw");
+
+            if is_permission == false || self.is_dao_paused() {
+                return false;
+            }
+
+            self.withdraw_private(asset, to_address, value, vote_id)
+        }
 
-            if erc_20_address == AccountId::from([0xee; 32]) {
-                return self.withdraw_native_token(to_address, value.into());
+        pub fn withdraw_private(&mut self, asset: Asset, to_address:AccountId, value:Balance, vote_id: Option<u64>) -> bool {
+            // funds earmarked for a budget can only leave through `spend_budget`.
+            if value > self.get_available_balance(asset) {
+                return false;
             }
+
+            self.transfer_out(asset, to_address, value, vote_id).is_some()
+        }
+
+        fn transfer_out(&mut self, asset: Asset, to_address:AccountId, value:Balance, vote_id: Option<u64>) -> Option<u64> {
+
             let from_address = self.vault_contract_address;
 
-            if self.visible_tokens.contains_key(&erc_20_address) {
+            let token_name = match asset {
+                Asset::Native => {
+                    let balance = self.env().balance();
+                    assert!(balance >= value, "balance is not enough");
 
+                    match self.env().transfer(to_address, value) {
+                        Err(_) => panic!("transfer failed!"),
+                        Ok(_) => {}
+                    }
+                    String::from(NATIVE_TOKEN_NAME)
+                }
+                Asset::Erc20(erc_20_address) => {
+                    if !self.visible_tokens.contains_key(&erc_20_address) {
+                        return None;
+                    }
 
-                let _caller = self.env().caller();
+                    let mut erc_20 = self.get_erc20_by_address(erc_20_address);
 
-                let _auth = self.get_auth_by_address(self.auth_contract_address);
-    
-                // let is_permission = auth.has_permission(caller,String::from("vault"),String::from("withdraw"));
-                let is_permission = true;
-    
-                if is_permission == false {
-                    return false;
+                    let token_name = (&erc_20).token_name().unwrap_or_default();
+
+                    let transfer_result  = erc_20.transfer(to_address, value, ink_prelude::vec::Vec::new());
+
+                    if transfer_result.is_err() {
+                        return None;
+                    }
+                    token_name
                 }
+            };
+
+            let transfer_id = self.record_transfer(1, asset, token_name.clone(), from_address, to_address, value, None, vote_id);
 
+            self.env().emit_event(WithdrawTokenEvent{
+                token_name,
+                to_address:to_address,
+                value:value,});
 
+            Some(transfer_id)
+        }
 
-                // let  balanceof =  self.get_balance_of(erc_20_address);
+        /// Vault balance of `asset` that is not earmarked for any budget.
+        #[ink(message)]
+        pub fn get_available_balance(&self, asset: Asset) -> Balance {
+            let earmarked = *self.earmarked.get(&asset).unwrap_or(&0);
+            self.get_balance_of(asset).saturating_sub(earmarked)
+        }
 
+        /// `None` while no auth contract is linked (the default address), nothing is permitted then.
+        fn auth(&self) -> Option<Auth> {
+            if self.auth_contract_address == AccountId::default() {
+                return None;
+            }
+            Some(self.get_auth_by_address(self.auth_contract_address))
+        }
 
-                //let mut erc_20 = self.get_erc20_by_address(*erc_20_address.unwrap());
-                let mut erc_20 = self.get_erc20_by_address(erc_20_address);
+        fn is_dao_paused(&self) -> bool {
+            self.auth().map_or(false, |auth| auth.is_paused())
+        }
+
+        fn has_vault_permission(&self, caller: AccountId, function_name: &str) -> bool {
+            match self.auth() {
+                None => false,
+                Some(auth) => auth.has_permission(caller, String::from("vault"), String::from(function_name)),
+            }
+        }
+
+        fn is_budget_spender(&self, caller: AccountId, spender: &BudgetSpender) -> bool {
+            match spender {
+                BudgetSpender::Account(account) => *account == caller,
+                BudgetSpender::Action(contract_name, function_name) => match self.auth() {
+                    None => false,
+                    Some(auth) => auth.has_permission(caller, contract_name.clone(), function_name.clone()),
+                },
+            }
+        }
 
-                let token_name = (&erc_20).name();
+        fn record_budget_entry(&mut self, budget_id: u64, entry_type: u8, value: Balance,
+                               to_address: Option<AccountId>, transfer_id: Option<u64>) {
+            let entry_id = self.budget_entries.len() as u64 + 1;
+            let entry_time = self.env().block_timestamp();
+            self.budget_entries.insert(entry_id, BudgetEntry {
+                entry_id,
+                budget_id,
+                entry_type,
+                value,
+                to_address,
+                transfer_id,
+                entry_time,
+            });
+        }
 
-                //erc_20.transfer_from(from_address,to_address, value);
-                
-                let transfer_result  = erc_20.transfer(to_address, value);
+        /// Creates a budget and earmarks `value` of the general balance for it.
+        /// Only callable with the `vault.budget` permission, normally held by the vote manager.
+        #[ink(message)]
+        pub fn create_budget(&mut self, name: String, asset: Asset, spender: BudgetSpender, value: Balance) -> Option<u64> {
+            let caller = self.env().caller();
+            if !self.has_vault_permission(caller, "budget") {
+                return None;
+            }
+            if value > self.get_available_balance(asset) {
+                return None;
+            }
 
-                if transfer_result == false {
-                    return false;
-                }
+            let budget_id = self.budget_count + 1;
+            self.budget_count = budget_id;
+            self.budgets.insert(budget_id, Budget {
+                budget_id,
+                name: name.clone(),
+                asset,
+                spender,
+                allocated: value,
+                spent: 0,
+            });
+            let earmarked = self.earmarked.entry(asset).or_insert(0);
+            *earmarked += value;
+            self.record_budget_entry(budget_id, 1, value, None, None);
+
+            self.env().emit_event(CreateBudgetEvent{
+                budget_id,
+                name,
+                value,
+            });
+            Some(budget_id)
+        }
+
+        /// Moves `value` of the general balance into the budget.
+        #[ink(message)]
+        pub fn top_up_budget(&mut self, budget_id: u64, value: Balance) -> bool {
+            let caller = self.env().caller();
+            if !self.has_vault_permission(caller, "budget") {
+                return false;
+            }
+            let asset = match self.budgets.get(&budget_id) {
+                None => return false,
+                Some(budget) => budget.asset,
+            };
+            if value > self.get_available_balance(asset) {
+                return false;
+            }
 
-                let transfer_id:u64 = (self.transfer_history.len()+1).into();
+            let budget = self.budgets.get_mut(&budget_id).unwrap();
+            budget.allocated += value;
+            let earmarked = self.earmarked.entry(asset).or_insert(0);
+            *earmarked += value;
+            self.record_budget_entry(budget_id, 1, value, None, None);
 
-                let transfer_time: u64 = self.env().block_timestamp();
+            self.env().emit_event(TopUpBudgetEvent{
+                budget_id,
+                value,
+            });
+            true
+        }
 
-                self.transfer_history.insert(transfer_id,
-                                             Transfer{
-                                                 transfer_direction:1,// 1: out 2: in
-                                                 token_name: token_name.clone(),
-                                                 transfer_id:transfer_id,
-                                                 from_address:from_address,
-                                                 to_address:to_address,
-                                                 value:value,
-                                                 transfer_time:transfer_time});
+        /// Returns `value` of the unspent budget to the general balance.
+        #[ink(message)]
+        pub fn reclaim_budget(&mut self, budget_id: u64, value: Balance) -> bool {
+            let caller = self.env().caller();
+            if !self.has_vault_permission(caller, "budget") {
+                return false;
+            }
+            let asset = match self.budgets.get_mut(&budget_id) {
+                None => return false,
+                Some(budget) => {
+                    if value > budget.allocated - budget.spent {
+                        return false;
+                    }
+                    budget.allocated -= value;
+                    budget.asset
+                }
+            };
+            let earmarked = self.earmarked.entry(asset).or_insert(0);
+            *earmarked = earmarked.saturating_sub(value);
+            self.record_budget_entry(budget_id, 2, value, None, None);
+
+            self.env().emit_event(ReclaimBudgetEvent{
+                budget_id,
+                value,
+            });
+            true
+        }
 
+        /// Pays `value` out of the budget, only callable by the budget's spender.
+        #[ink(message)]
+        pub fn spend_budget(&mut self, budget_id: u64, to_address: AccountId, value: Balance) -> bool {
+            let caller = self.env().caller();
+            if self.is_dao_paused() {
+                return false;
+            }
+            let (asset, remaining) = match self.budgets.get(&budget_id) {
+                None => return false,
+                Some(budget) => {
+                    if !self.is_budget_spender(caller, &budget.spender) {
+                        return false;
+                    }
+                    (budget.asset, budget.allocated - budget.spent)
+                }
+            };
+            if value > remaining {
+                return false;
+            }
 
+            let transfer_id = match self.transfer_out(asset, to_address, value, None) {
+                None => return false,
+                Some(transfer_id) => transfer_id,
+            };
+            let budget = self.budgets.get_mut(&budget_id).unwrap();
+            budget.spent += value;
+            let earmarked = self.earmarked.entry(asset).or_insert(0);
+            *earmarked = earmarked.saturating_sub(value);
+            self.record_budget_entry(budget_id, 3, value, Some(to_address), Some(transfer_id));
+
+            self.env().emit_event(SpendBudgetEvent{
+                budget_id,
+                spender: caller,
+                to_address,
+                value,
+            });
+            true
+        }
 
+        /// Sets the moderator approval ceiling and escalation vote parameters for
+        /// reimbursement requests in `asset`. Needs the `vault.policy` permission.
+        #[ink(message)]
+        pub fn set_request_policy(&mut self, asset: Asset, ceiling: Balance, vote_time: u64,
+                                  support_require_num: u64, min_require_num: u64) -> bool {
+            let caller = self.env().caller();
+            if !self.has_vault_permission(caller, "policy") {
+                return false;
+            }
+            self.request_policies.insert(asset, RequestPolicy {
+                ceiling,
+                vote_time,
+                support_require_num,
+                min_require_num,
+            });
+            true
+        }
 
-                self.env().emit_event(WithdrawTokenEvent{
-                    token_name: token_name.clone(),
-                    to_address:to_address,
-                    value:value,});
+        #[ink(message)]
+        pub fn get_request_policy(&self, asset: Asset) -> Option<RequestPolicy> {
+            self.request_policies.get(&asset).cloned()
+        }
 
-                true
+        /// Files a reimbursement request for the calling org member. Requests above
+        /// the asset's ceiling are escalated to a vote right away.
+        #[ink(message)]
+        pub fn file_request(&mut self, asset: Asset, value: Balance, evidence: Hash) -> Option<u64> {
+            let caller = self.env().caller();
+            let org = self.get_orgmanager_by_address(self.org_contract_address);
+            let (is_member, is_moderator, is_owner) = org.check_role_by_account(caller);
+            if !is_member && !is_moderator && !is_owner {
+                return None;
+            }
+            let policy = self.request_policies.get(&asset)?.clone();
+            let request_id = self.create_request(caller, asset, value, evidence)?;
+            if value > policy.ceiling {
+                let vote_id = self.open_request_vote(request_id, &policy);
+                self.escalate_request(request_id, vote_id);
+            }
+            Some(request_id)
+        }
 
-            } else{
-                false
+        fn create_request(&mut self, caller: AccountId, asset: Asset, value: Balance, evidence: Hash) -> Option<u64> {
+            if value == 0 {
+                return None;
             }
+
+            let request_id = self.request_count + 1;
+            self.request_count = request_id;
+            let request = ReimbursementRequest {
+                request_id,
+                applicant: caller,
+                asset,
+                value,
+                evidence,
+                status: 0,
+                reviewer: None,
+                vote_id: None,
+                transfer_id: None,
+                create_time: self.env().block_timestamp(),
+            };
+            self.env().emit_event(FileRequestEvent{
+                request_id,
+                applicant: caller,
+                value,
+            });
+            self.requests.insert(request_id, request);
+            Some(request_id)
         }
 
-        pub fn withdraw_native_token(&mut self, to_address:AccountId, value:u128) -> bool {
+        fn escalate_request(&mut self, request_id: u64, vote_id: u64) {
+            let request = self.requests.get_mut(&request_id).unwrap();
+            request.status = 3;
+            request.vote_id = Some(vote_id);
+            self.env().emit_event(EscalateRequestEvent{
+                request_id,
+                vote_id,
+            });
+        }
 
-            let from_address = self.vault_contract_address;
-            let balance = self.env().balance();
-            assert!(balance >= value, "balance is not enough");
+        fn open_request_vote(&self, request_id: u64, policy: &RequestPolicy) -> u64 {
+            let vote_contract_address = self.vote_contract_address.expect("vote contract not set");
+            build_call::<ink_env::DefaultEnvironment>()
+                .callee(vote_contract_address)
+                .gas_limit(0)
+                .transferred_value(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(NEW_REQUEST_VOTE_SELECTOR))
+                        .push_arg(request_id)
+                        .push_arg(policy.vote_time)
+                        .push_arg(policy.support_require_num)
+                        .push_arg(policy.min_require_num)
+                )
+                .returns::<ReturnType<u64>>()
+                .fire()
+                .expect("failed to open the request vote")
+        }
 
-            let _caller = self.env().caller();
+        fn is_moderator_or_owner(&self, account: AccountId) -> bool {
+            let org = self.get_orgmanager_by_address(self.org_contract_address);
+            let (_is_member, is_moderator, is_owner) = org.check_role_by_account(account);
+            is_moderator || is_owner
+        }
 
-            let _auth = self.get_auth_by_address(self.auth_contract_address);
+        /// Moderator approval, pays a pending request within the ceiling. Moderators
+        /// can't approve their own requests.
+        #[ink(message)]
+        pub fn approve_request(&mut self, request_id: u64) -> bool {
+            let caller = self.env().caller();
+            if !self.is_moderator_or_owner(caller) || self.is_dao_paused() {
+                return false;
+            }
+            self.approve_request_private(request_id, caller)
+        }
 
-            // let is_permission = auth.has_permission(caller,String::from("vault"),String::from("withdraw"));
-            let is_permission = true;
+        fn approve_request_private(&mut self, request_id: u64, reviewer: AccountId) -> bool {
+            match self.requests.get(&request_id) {
+                Some(request) if request.status == 0 && request.applicant != reviewer => {}
+                _ => return false,
+            }
+            self.pay_out_request(request_id, reviewer)
+        }
 
-            if is_permission == false {
+        /// Pays an escalated request once its vote passed, needs the `vault.withdraw` permission.
+        #[ink(message)]
+        pub fn pay_request(&mut self, request_id: u64) -> bool {
+            let caller = self.env().caller();
+            if !self.has_vault_permission(caller, "withdraw") || self.is_dao_paused() {
                 return false;
             }
+            self.pay_request_private(request_id, caller)
+        }
 
-            match self.env().transfer(to_address, value.into()) {
-                Err(_) => panic!("transfer failed!"),
-                Ok(_) => {}
+        fn pay_request_private(&mut self, request_id: u64, reviewer: AccountId) -> bool {
+            match self.requests.get(&request_id) {
+                Some(request) if request.status == 3 => {}
+                _ => return false,
             }
+            self.pay_out_request(request_id, reviewer)
+        }
 
-            let value2 = value as u64;
-            let transfer_id:u64 = (self.transfer_history.len()+1).into();
+        fn pay_out_request(&mut self, request_id: u64, reviewer: AccountId) -> bool {
+            let request = self.requests.get(&request_id).unwrap().clone();
+            if request.value > self.get_available_balance(request.asset) {
+                return false;
+            }
+            let transfer_id = match self.transfer_out(request.asset, request.applicant, request.value, request.vote_id) {
+                None => return false,
+                Some(transfer_id) => transfer_id,
+            };
+            let stored = self.requests.get_mut(&request_id).unwrap();
+            stored.status = 1;
+            stored.reviewer = Some(reviewer);
+            stored.transfer_id = Some(transfer_id);
+            self.env().emit_event(ReviewRequestEvent{
+                request_id,
+                reviewer,
+                status: 1,
+            });
+            true
+        }
 
-            let transfer_time: u64 = self.env().block_timestamp();
+        /// Moderator rejection of a pending request, escalated ones are settled by their vote.
+        #[ink(message)]
+        pub fn reject_request(&mut self, request_id: u64) -> bool {
+            let caller = self.env().caller();
+            if !self.is_moderator_or_owner(caller) || self.is_dao_paused() {
+                return false;
+            }
+            self.reject_request_private(request_id, caller)
+        }
 
-            self.transfer_history.insert(transfer_id,
-                                         Transfer{
-                                             transfer_direction:1,// 1: out 2: in
-                                             token_name:String::from("subDAO"),
-                                             transfer_id:transfer_id,
-                                             from_address:from_address,
-                                             to_address:to_address,
-                                             value:value2,
-                                             transfer_time:transfer_time});
+        fn reject_request_private(&mut self, request_id: u64, caller: AccountId) -> bool {
+            self.close_request(request_id, caller, 0)
+        }
+
+        /// Rejects an escalated request whose vote failed, needs the `vault.withdraw` permission.
+        #[ink(message)]
+        pub fn decline_request(&mut self, request_id: u64) -> bool {
+            let caller = self.env().caller();
+            if !self.has_vault_permission(caller, "withdraw") {
+                return false;
+            }
+            self.close_request(request_id, caller, 3)
+        }
+
+        fn close_request(&mut self, request_id: u64, caller: AccountId, status: u8) -> bool {
+            match self.requests.get_mut(&request_id) {
+                Some(request) if request.status == status => {
+                    request.status = 2;
+                    request.reviewer = Some(caller);
+                }
+                _ => return false,
+            }
+            self.env().emit_event(ReviewRequestEvent{
+                request_id,
+                reviewer: caller,
+                status: 2,
+            });
+            true
+        }
 
+        #[ink(message)]
+        pub fn get_request(&self, request_id: u64) -> Option<ReimbursementRequest> {
+            self.requests.get(&request_id).cloned()
+        }
 
+        /// All requests, newest first.
+        #[ink(message)]
+        pub fn get_request_list(&self) -> ink_prelude::vec::Vec<ReimbursementRequest> {
+            let mut v:ink_prelude::vec::Vec<ReimbursementRequest> = ink_prelude::vec::Vec::new();
+            for (_, request) in &self.requests {
+                v.push(request.clone());
+            }
+            v.sort_by(|a, b| b.request_id.cmp(&a.request_id));
+            v
+        }
+
+        /// Burns `shares` of the caller's DAO tokens and pays out the same fraction of
+        /// the total supply from every visible token and the native balance. Budget
+        /// funds are excluded. Not allowed while the caller has a pending "yes" vote
+        /// on a proposal that still has to be executed. The shares are burned through
+        /// `admin_burn` with the vault's `erc20.burn` permission, so soulbound tokens work too.
+        #[ink(message)]
+        pub fn ragequit(&mut self, shares: Balance) -> bool {
+            let caller = self.env().caller();
+            let dao_token = match self.dao_token {
+                None => return false,
+                Some(dao_token) => dao_token,
+            };
+            if shares == 0 || self.is_dao_paused() || self.has_pending_approval(caller) {
+                return false;
+            }
+            let mut erc_20 = self.get_erc20_by_address(dao_token);
+            let total_supply = erc_20.total_supply();
+            if erc_20.balance_of(caller) < shares
+                || erc_20.allowance(caller, self.vault_contract_address) < shares {
+                return false;
+            }
 
+            let mut assets:ink_prelude::vec::Vec<Asset> = ink_prelude::vec::Vec::new();
+            for key in self.visible_tokens.keys() {
+                assets.push(Asset::Erc20(*key));
+            }
+            assets.push(Asset::Native);
+            let mut payouts:ink_prelude::vec::Vec<(Asset, Balance)> = ink_prelude::vec::Vec::new();
+            for asset in assets {
+                let available = self.get_available_balance(asset);
+                let payout = Self::pro_rata(available, shares, total_supply);
+                if payout > 0 {
+                    payouts.push((asset, payout));
+                }
+            }
 
-            self.env().emit_event(WithdrawTokenEvent{
-                token_name:String::from("subDAO"),
-                to_address:to_address,
-                value:value2,});
+            assert!(erc_20.admin_burn(caller, shares).is_ok(), "burn failed");
+            for (asset, payout) in payouts {
+                assert!(self.transfer_out(asset, caller, payout, None).is_some(), "ragequit payout failed");
+            }
 
+            self.env().emit_event(RagequitEvent{
+                member: caller,
+                shares,
+            });
             true
         }
 
+        fn has_pending_approval(&self, account: AccountId) -> bool {
+            let vote_contract_address = match self.vote_contract_address {
+                None => return false,
+                Some(vote_contract_address) => vote_contract_address,
+            };
+            build_call::<ink_env::DefaultEnvironment>()
+                .callee(vote_contract_address)
+                .gas_limit(0)
+                .transferred_value(0)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(HAS_PENDING_APPROVAL_SELECTOR))
+                        .push_arg(account)
+                )
+                .returns::<ReturnType<bool>>()
+                .fire()
+                // fail closed, an unreachable vote manager must not allow an exit
+                .unwrap_or(true)
+        }
+
+        /// `inflows - outflows`, saturating at the `i128` bounds.
+        fn net_change(inflows: Balance, outflows: Balance) -> i128 {
+            if inflows >= outflows {
+                i128::try_from(inflows - outflows).unwrap_or(i128::MAX)
+            } else {
+                i128::try_from(outflows - inflows).map(|change| -change).unwrap_or(i128::MIN)
+            }
+        }
+
+        /// `balance * shares / total_supply`, without overflowing on large balances.
+        fn pro_rata(balance: Balance, shares: Balance, total_supply: Balance) -> Balance {
+            if total_supply == 0 || shares > total_supply {
+                return 0;
+            }
+            match balance.checked_mul(shares) {
+                Some(product) => product / total_supply,
+                None => balance / total_supply * shares,
+            }
+        }
+
+        #[ink(message)]
+        pub fn get_budget(&self, budget_id: u64) -> Option<Budget> {
+            self.budgets.get(&budget_id).cloned()
+        }
 
+        #[ink(message)]
+        pub fn get_budget_list(&self) -> ink_prelude::vec::Vec<Budget> {
+            let mut v:ink_prelude::vec::Vec<Budget> = ink_prelude::vec::Vec::new();
+            for (_, budget) in &self.budgets {
+                v.push(budget.clone());
+            }
+            v
+        }
+
+        /// Top-ups, reclaims and spends of a budget, oldest first.
+        #[ink(message)]
+        pub fn get_budget_history(&self, budget_id: u64) -> ink_prelude::vec::Vec<BudgetEntry> {
+            let mut v:ink_prelude::vec::Vec<BudgetEntry> = ink_prelude::vec::Vec::new();
+            for (_, entry) in &self.budget_entries {
+                if entry.budget_id == budget_id {
+                    v.push(entry.clone());
+                }
+            }
+            v.sort_by_key(|entry| entry.entry_id);
+            v
+        }
+
+        fn record_transfer(&mut self, transfer_direction:u64, asset: Asset, token_name: String,
+                           from_address:AccountId, to_address:AccountId, value:Balance,
+                           memo: Option<String>, vote_id: Option<u64>) -> u64 {
+            let transfer_id:u64 = (self.transfer_history.len()+1).into();
+            let transfer_time: u64 = self.env().block_timestamp();
+
+            self.transfer_history.insert(transfer_id,
+                                         Transfer{
+                                             transfer_direction,// 1: out 2: in 3: unattributed inflow 4: unattributed outflow
+                                             asset,
+                                             token_name,
+                                             transfer_id,
+                                             from_address,
+                                             to_address,
+                                             value,
+                                             transfer_time,
+                                             memo,
+                                             vote_id});
+
+            let ledger_balance = self.ledger.entry(asset).or_insert(0);
+            if transfer_direction == 1 || transfer_direction == 4 {
+                *ledger_balance = ledger_balance.saturating_sub(value);
+            } else {
+                *ledger_balance = ledger_balance.saturating_add(value);
+            }
+            transfer_id
+        }
+
+        /// Balance of `asset` according to the vault's own transfer history.
+        #[ink(message)]
+        pub fn get_ledger_balance(&self, asset: Asset) -> Balance {
+            *self.ledger.get(&asset).unwrap_or(&0)
+        }
+
+        /// Compares the ledger with the on-chain balance and records any difference,
+        /// e.g. tokens sent straight to the vault, as an unattributed transfer.
+        /// Returns the id of the recorded transfer, `None` when both agree.
+        #[ink(message)]
+        pub fn reconcile(&mut self, asset: Asset) -> Option<u64> {
+            let token_name = match asset {
+                Asset::Native => String::from(NATIVE_TOKEN_NAME),
+                Asset::Erc20(erc_20_address) => {
+                    if !self.tokens.contains_key(&erc_20_address) {
+                        return None;
+                    }
+                    self.get_erc20_by_address(erc_20_address).token_name().unwrap_or_default()
+                }
+            };
+            let onchain_balance = self.get_balance_of(asset);
+            let ledger_balance = self.get_ledger_balance(asset);
+            let vault_address = self.vault_contract_address;
+
+            let transfer_id = if onchain_balance > ledger_balance {
+                let value = onchain_balance - ledger_balance;
+                let transfer_id = self.record_transfer(3, asset, token_name.clone(), AccountId::default(), vault_address,
+                                                       value, Some(String::from("unattributed inflow")), None);
+                self.env().emit_event(ReconcileEvent{
+                    token_name,
+                    inflow: true,
+                    value,
+                });
+                transfer_id
+            } else if onchain_balance < ledger_balance {
+                let value = ledger_balance - onchain_balance;
+                let transfer_id = self.record_transfer(4, asset, token_name.clone(), vault_address, AccountId::default(),
+                                                       value, Some(String::from("unattributed outflow")), None);
+                self.env().emit_event(ReconcileEvent{
+                    token_name,
+                    inflow: false,
+                    value,
+                });
+                transfer_id
+            } else {
+                return None;
+            };
+            Some(transfer_id)
+        }
 
         #[ink(message)]
         pub fn get_transfer_history(&self) -> ink_prelude::vec::Vec<Transfer> {
@@ -512,6 +1346,91 @@ mod vault {
             temp_vec
         }
 
+        /// Per-asset report for transfers with `start_time <= transfer_time < end_time`.
+        #[ink(message)]
+        pub fn get_treasury_report(&self, asset: Asset, start_time: u64, end_time: u64, top_n: u32) -> TreasuryReport {
+            let mut opening_balance: Balance = 0;
+            let mut inflows: Balance = 0;
+            let mut outflows: Balance = 0;
+            let mut counterparties: ink_prelude::collections::BTreeMap<AccountId, Balance> = ink_prelude::collections::BTreeMap::new();
+            let mut outflow_vote_ids: ink_prelude::vec::Vec<u64> = ink_prelude::vec::Vec::new();
+
+            for (_, transfer) in &self.transfer_history {
+                if transfer.asset != asset || transfer.transfer_time >= end_time {
+                    continue;
+                }
+                let outgoing = transfer.transfer_direction == 1 || transfer.transfer_direction == 4;
+                if transfer.transfer_time < start_time {
+                    if outgoing {
+                        opening_balance = opening_balance.saturating_sub(transfer.value);
+                    } else {
+                        opening_balance = opening_balance.saturating_add(transfer.value);
+                    }
+                    continue;
+                }
+
+                let counterparty = if outgoing {
+                    outflows = outflows.saturating_add(transfer.value);
+                    if let Some(vote_id) = transfer.vote_id {
+                        if !outflow_vote_ids.contains(&vote_id) {
+                            outflow_vote_ids.push(vote_id);
+                        }
+                    }
+                    transfer.to_address
+                } else {
+                    inflows = inflows.saturating_add(transfer.value);
+                    transfer.from_address
+                };
+                // unattributed entries have no counterparty
+                if transfer.transfer_direction <= 2 {
+                    let volume = counterparties.entry(counterparty).or_insert(0);
+                    *volume = volume.saturating_add(transfer.value);
+                }
+            }
+
+            let mut top_counterparties: ink_prelude::vec::Vec<(AccountId, Balance)> = counterparties.into_iter().collect();
+            top_counterparties.sort_by(|a, b| b.1.cmp(&a.1));
+            top_counterparties.truncate(top_n as usize);
+            outflow_vote_ids.sort();
+
+            TreasuryReport {
+                asset,
+                start_time,
+                end_time,
+                opening_balance,
+                inflows,
+                outflows,
+                closing_balance: opening_balance.saturating_add(inflows).saturating_sub(outflows),
+                net_change: Self::net_change(inflows, outflows),
+                top_counterparties,
+                outflow_vote_ids,
+            }
+        }
+
+        /// `get_treasury_report` for every listed token and the native token.
+        #[ink(message)]
+        pub fn get_treasury_reports(&self, start_time: u64, end_time: u64, top_n: u32) -> ink_prelude::vec::Vec<TreasuryReport> {
+            let mut v:ink_prelude::vec::Vec<TreasuryReport> = ink_prelude::vec::Vec::new();
+            for asset in self.get_token_list() {
+                v.push(self.get_treasury_report(asset, start_time, end_time, top_n));
+            }
+            v
+        }
+
+        /// Depositors of `asset` ordered by their total deposited amount, at most `count` entries.
+        #[ink(message)]
+        pub fn get_top_depositors(&self, asset: Asset, count: u32) -> ink_prelude::vec::Vec<(AccountId, Balance)> {
+            let mut depositors: ink_prelude::vec::Vec<(AccountId, Balance)> = ink_prelude::vec::Vec::new();
+            for ((deposit_asset, depositor), total) in &self.depositor_totals {
+                if *deposit_asset == asset {
+                    depositors.push((*depositor, *total));
+                }
+            }
+            depositors.sort_by(|a, b| b.1.cmp(&a.1));
+            depositors.truncate(count as usize);
+            depositors
+        }
+
 
     }
 
@@ -531,10 +1450,9 @@ mod vault {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
-            // Create a new contract instance.
-            // FIXME: using alice instead of auth, please be caution!!
-            let mut vault_manager = VaultManager::new(accounts.alice, accounts.alice);
-            vault_manager.add_vault_token(accounts.bob);
+            // Create a new contract instance, without an auth contract linked.
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            vault_manager.add_vault_token_private(accounts.bob);
             assert_eq!(vault_manager.tokens.len(), 1);
         }
 
@@ -544,11 +1462,10 @@ mod vault {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
-            // Create a new contract instance.
-            // FIXME: using alice instead of auth, please be caution!!
-            let mut vault_manager = VaultManager::new(accounts.alice, accounts.alice);
-            vault_manager.add_vault_token(accounts.bob);
-            vault_manager.remove_vault_token(accounts.bob);
+            // Create a new contract instance, without an auth contract linked.
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            vault_manager.add_vault_token_private(accounts.bob);
+            vault_manager.remove_vault_token_private(accounts.bob);
             assert_eq!(vault_manager.tokens.len(), 1);
             assert_eq!(vault_manager.visible_tokens.len(), 0);
         }
@@ -559,11 +1476,10 @@ mod vault {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
-            // Create a new contract instance.
-            // FIXME: using alice instead of auth, please be caution!!
-            let mut vault_manager = VaultManager::new(accounts.alice, accounts.alice);
-            vault_manager.add_vault_token(accounts.bob);
-            vault_manager.add_vault_token(accounts.alice);
+            // Create a new contract instance, without an auth contract linked.
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            vault_manager.add_vault_token_private(accounts.bob);
+            vault_manager.add_vault_token_private(accounts.alice);
             assert_eq!(vault_manager.get_token_list().len(), 2);
         }
 
@@ -573,11 +1489,10 @@ mod vault {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
-            // Create a new contract instance.
-            // FIXME: using alice instead of auth, please be caution!!
-            let mut vault_manager = VaultManager::new(accounts.alice, accounts.alice);
-            vault_manager.add_vault_token(accounts.bob);
-            assert_eq!(vault_manager.get_balance_of(accounts.bob), 0);
+            // Create a new contract instance, without an auth contract linked.
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            vault_manager.add_vault_token_private(accounts.bob);
+            assert_eq!(vault_manager.get_balance_of(Asset::Erc20(accounts.bob)), 0);
         }
 
 
@@ -587,12 +1502,11 @@ mod vault {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
-            // Create a new contract instance.
-            // FIXME: using alice instead of auth, please be caution!!
-            let mut vault_manager = VaultManager::new(accounts.alice, accounts.alice);
-            vault_manager.add_vault_token(accounts.bob);
-            vault_manager.deposit(accounts.bob,accounts.alice,100);
-            assert_eq!(vault_manager.get_balance_of(accounts.bob),100);
+            // Create a new contract instance, without an auth contract linked.
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            vault_manager.add_vault_token_private(accounts.bob);
+            vault_manager.deposit(Asset::Erc20(accounts.bob),100,None);
+            assert_eq!(vault_manager.get_balance_of(Asset::Erc20(accounts.bob)),100);
 
         }
 
@@ -602,13 +1516,18 @@ mod vault {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
-            // Create a new contract instance.
-            // FIXME: using alice instead of auth, please be caution!!
-            let mut vault_manager = VaultManager::new(accounts.alice, accounts.alice);
-            vault_manager.add_vault_token(accounts.bob);
-            vault_manager.deposit(accounts.bob,accounts.eve,1000);
-            vault_manager.withdraw(accounts.bob,accounts.alice,100);
-            assert_eq!(vault_manager.get_balance_of(accounts.bob),900);
+            // Create a new contract instance, without an auth contract linked.
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            let vault = vault_manager.vault_contract_address;
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(vault, 1000)
+                .expect("Cannot set account balance");
+            // nobody holds `vault.withdraw` without an auth contract
+            assert!(!vault_manager.withdraw(Asset::Native,accounts.alice,100,None));
+            assert!(vault_manager.withdraw_private(Asset::Native,accounts.alice,100,None));
+            assert_eq!(vault_manager.get_balance_of(Asset::Native),900);
+            // earmarked funds stay
+            vault_manager.earmarked.insert(Asset::Native, 850);
+            assert!(!vault_manager.withdraw_private(Asset::Native,accounts.alice,100,None));
 
         }
 
@@ -618,15 +1537,179 @@ mod vault {
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                     .expect("Cannot get accounts");
-            // Create a new contract instance.
-            // FIXME: using alice instead of auth, please be caution!!
-            let mut vault_manager = VaultManager::new(accounts.alice, accounts.alice);
-            vault_manager.add_vault_token(accounts.bob);
-            vault_manager.deposit(accounts.bob,accounts.eve,1000);
-            vault_manager.withdraw(accounts.bob,accounts.alice,100);
+            // Create a new contract instance, without an auth contract linked.
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            let vault = vault_manager.vault_contract_address;
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(vault, 1000)
+                .expect("Cannot set account balance");
+            assert!(deposit_native_as(&mut vault_manager, accounts.bob, 1000));
+            assert!(vault_manager.withdraw_private(Asset::Native,accounts.alice,100,None));
             assert_eq!(vault_manager.get_transfer_history().len(),2);
 
         }
 
+
+        const DEFAULT_CALLEE_HASH: [u8; 32] = [0x07; 32];
+        const DEFAULT_GAS_LIMIT: Balance = 1_000_000;
+
+        /// `caller` deposits `value` of the native token, sent along with the call.
+        fn deposit_native_as(vault_manager: &mut VaultManager, caller: AccountId, value: Balance) -> bool {
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller,
+                AccountId::from(DEFAULT_CALLEE_HASH),
+                DEFAULT_GAS_LIMIT,
+                value,
+                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])),
+            );
+            let result = vault_manager.deposit(Asset::Native, value, None);
+            ink_env::test::pop_execution_context();
+            result
+        }
+
+        #[ink::test]
+        fn top_depositors_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            assert!(deposit_native_as(&mut vault_manager, accounts.bob, 100));
+            assert!(deposit_native_as(&mut vault_manager, accounts.eve, 300));
+            assert!(deposit_native_as(&mut vault_manager, accounts.alice, 200));
+            // deposits add up per caller
+            assert!(deposit_native_as(&mut vault_manager, accounts.bob, 150));
+
+            let top = vault_manager.get_top_depositors(Asset::Native, 2);
+            assert_eq!(top, vec![(accounts.eve, 300), (accounts.bob, 250)]);
+            assert_eq!(vault_manager.get_top_depositors(Asset::Erc20(accounts.bob), 2).len(), 0);
+        }
+
+
+        #[ink::test]
+        fn available_balance_excludes_budgets() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            let balance = vault_manager.get_balance_of(Asset::Native);
+            vault_manager.earmarked.insert(Asset::Native, 10);
+            assert_eq!(vault_manager.get_available_balance(Asset::Native), balance.saturating_sub(10));
+        }
+
+
+        #[ink::test]
+        fn reconcile_native_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            let balance = vault_manager.get_balance_of(Asset::Native);
+            assert_eq!(vault_manager.get_ledger_balance(Asset::Native), 0);
+
+            assert!(vault_manager.reconcile(Asset::Native).is_some());
+            assert_eq!(vault_manager.get_ledger_balance(Asset::Native), balance);
+            assert_eq!(vault_manager.get_transfer_history()[0].transfer_direction, 3);
+            assert_eq!(vault_manager.reconcile(Asset::Native), None);
+        }
+
+        #[ink::test]
+        fn treasury_report_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            let vault = vault_manager.vault_contract_address;
+            let name = String::from(NATIVE_TOKEN_NAME);
+            vault_manager.record_transfer(2, Asset::Native, name.clone(), accounts.bob, vault, 100, None, None);
+            let start_time = vault_manager.get_transfer_history()[0].transfer_time + 1;
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>().expect("Cannot advance block");
+            vault_manager.record_transfer(2, Asset::Native, name.clone(), accounts.eve, vault, 50, None, None);
+            vault_manager.record_transfer(1, Asset::Native, name.clone(), vault, accounts.bob, 30, None, Some(7));
+            vault_manager.record_transfer(2, Asset::Erc20(accounts.bob), name.clone(), accounts.bob, vault, 1000, None, None);
+
+            let report = vault_manager.get_treasury_report(Asset::Native, start_time, u64::MAX, 1);
+            assert_eq!(report.opening_balance, 100);
+            assert_eq!(report.inflows, 50);
+            assert_eq!(report.outflows, 30);
+            assert_eq!(report.closing_balance, 120);
+            assert_eq!(report.net_change, 20);
+            assert_eq!(report.top_counterparties, vec![(accounts.eve, 50)]);
+            assert_eq!(report.outflow_vote_ids, vec![7]);
+        }
+
+        #[ink::test]
+        fn request_approval_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            let request_id = vault_manager.create_request(accounts.bob, Asset::Native, 10, Hash::from([0x01; 32])).unwrap();
+            assert_eq!(vault_manager.create_request(accounts.bob, Asset::Native, 0, Hash::from([0x01; 32])), None);
+            // no self-approval
+            assert!(!vault_manager.approve_request_private(request_id, accounts.bob));
+            assert!(vault_manager.approve_request_private(request_id, accounts.alice));
+            let request = vault_manager.get_request(request_id).unwrap();
+            assert_eq!(request.status, 1);
+            assert_eq!(request.reviewer, Some(accounts.alice));
+            assert!(request.transfer_id.is_some());
+            // paid only once
+            assert!(!vault_manager.approve_request_private(request_id, accounts.alice));
+            assert!(!vault_manager.reject_request_private(request_id, accounts.alice));
+        }
+
+        #[ink::test]
+        fn request_rejection_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            let request_id = vault_manager.create_request(accounts.bob, Asset::Native, 10, Hash::from([0x01; 32])).unwrap();
+            assert!(vault_manager.reject_request_private(request_id, accounts.alice));
+            assert_eq!(vault_manager.get_request(request_id).unwrap().status, 2);
+            assert!(!vault_manager.approve_request_private(request_id, accounts.alice));
+            assert_eq!(vault_manager.get_transfer_history().len(), 0);
+        }
+
+        #[ink::test]
+        fn request_escalation_works() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot get accounts");
+            // no auth contract linked
+            let mut vault_manager = VaultManager::new(accounts.alice, AccountId::default());
+            let request_id = vault_manager.create_request(accounts.bob, Asset::Native, 10, Hash::from([0x01; 32])).unwrap();
+            // pending requests are paid by moderators, not by a vote
+            assert!(!vault_manager.pay_request_private(request_id, accounts.alice));
+            vault_manager.escalate_request(request_id, 7);
+            assert_eq!(vault_manager.get_request(request_id).unwrap().vote_id, Some(7));
+            // escalated requests can't be approved or rejected by a moderator
+            assert!(!vault_manager.approve_request_private(request_id, accounts.alice));
+            assert!(!vault_manager.reject_request_private(request_id, accounts.alice));
+            assert!(vault_manager.pay_request_private(request_id, accounts.alice));
+            assert_eq!(vault_manager.get_request(request_id).unwrap().status, 1);
+            assert_eq!(vault_manager.get_transfer_history()[0].vote_id, Some(7));
+        }
+
+        #[ink::test]
+        fn pro_rata_works() {
+            assert_eq!(VaultManager::pro_rata(1000, 25, 100), 250);
+            assert_eq!(VaultManager::pro_rata(1000, 0, 100), 0);
+            assert_eq!(VaultManager::pro_rata(1000, 25, 0), 0);
+            assert_eq!(VaultManager::pro_rata(Balance::MAX, 1, 2), Balance::MAX / 2);
+        }
+
+        #[ink::test]
+        fn net_change_saturates() {
+            assert_eq!(VaultManager::net_change(50, 30), 20);
+            assert_eq!(VaultManager::net_change(30, 50), -20);
+            assert_eq!(VaultManager::net_change(Balance::MAX, 0), i128::MAX);
+            assert_eq!(VaultManager::net_change(0, Balance::MAX), i128::MIN);
+        }
+
     }
 }
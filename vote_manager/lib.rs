@@ -2,6 +2,7 @@
 
 extern crate alloc;
 pub use self::vote_manager::VoteManager;
+pub use self::vote_manager::VoteAction;
 use ink_lang as ink;
 
 #[ink::contract]
@@ -14,7 +15,8 @@ mod vote_manager {
     use alloc::string::String;
     use alloc::vec::Vec;
     use auth::Auth;
-    use vault::VaultManager;
+    use org::OrgManager;
+    use vault::{Asset, BudgetSpender, VaultManager, NATIVE_TOKEN_NAME, NATIVE_TOKEN_SYMBOL};
 
     use ink_storage::{
         collections::{HashMap as StorageHashMap, Vec as StorageVec},
@@ -42,6 +44,72 @@ mod vote_manager {
         yea: u64,
     }
 
+    /// What a vote triggers besides a plain transfer once it passes.
+    #[derive(Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(
+            Debug,
+            scale_info::TypeInfo,
+            ink_storage::traits::StorageLayout
+        )
+    )]
+    pub enum VoteAction {
+        CreateBudget {
+            name: String,
+            asset: Asset,
+            spender: BudgetSpender,
+            value: Balance,
+        },
+        TopUpBudget {
+            budget_id: u64,
+            value: Balance,
+        },
+        ReclaimBudget {
+            budget_id: u64,
+            value: Balance,
+        },
+        // lift the DAO-wide emergency pause
+        Unpause,
+        // pay an escalated vault reimbursement request
+        PayRequest {
+            request_id: u64,
+        },
+        SetRequestPolicy {
+            asset: Asset,
+            ceiling: Balance,
+            vote_time: u64,
+            support_require_num: u64,
+            min_require_num: u64,
+        },
+        // mint DAO tokens, the vote manager is a minter of the DAO token
+        Mint {
+            to: AccountId,
+            value: Balance,
+        },
+        // manage the DAO token's minters, the vote manager holds `erc20.minter`
+        AddMinter {
+            minter: AccountId,
+        },
+        RemoveMinter {
+            minter: AccountId,
+        },
+        // remove a member or moderator from the org
+        RemoveMember {
+            member: AccountId,
+        },
+        // define an org role, the vote manager holds `org.role`
+        CreateRole {
+            name: String,
+            actions: Vec<(String, String)>,
+        },
+        // admit a pending applicant to the org
+        AdmitMember {
+            member: AccountId,
+            application_id: u64,
+        },
+    }
+
     #[derive(scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
     #[cfg_attr(
         feature = "std",
@@ -64,9 +132,10 @@ mod vote_manager {
         support_require_num: u64,
         min_require_num: u64,
         support_num: u64,
-        erc20_address: AccountId,
+        asset: Asset,
         to_address: AccountId,
-        value: u64,
+        value: Balance,
+        action: Option<VoteAction>,
         choice_index_lo: u32,
         choice_index_ho: u32,
         status: u32,
@@ -95,12 +164,13 @@ mod vote_manager {
         min_require_num: u64,
         support_num: u64,
         choices: String,
-        erc20_address: AccountId,
+        asset: Asset,
         erc20_symbol: String,
         erc20_name: String,
-        erc20_balance: u64,
+        erc20_balance: Balance,
         to_address: AccountId,
-        transfer_value: u64,
+        transfer_value: Balance,
+        action: Option<VoteAction>,
         status: u32, //0 : open status, 1 : success , 2 : Failure support_num < min_req, 3 : Failure transfer token failure.
     }
 
@@ -108,6 +178,7 @@ mod vote_manager {
     pub struct VoteManager {
         vault: VaultManager,
         auth: Auth,
+        org: OrgManager,
         votes_length: u64,
         votes: StorageHashMap<VoteId, Vote>,
         voters: StorageHashMap<(VoteId, AccountId), ChoiceId>,
@@ -143,12 +214,14 @@ mod vote_manager {
 
     impl VoteManager {
         #[ink(constructor)]
-        pub fn new(vault_address: AccountId, auth_address: AccountId) -> Self {
+        pub fn new(vault_address: AccountId, auth_address: AccountId, org_address: AccountId) -> Self {
             let vault_instance = ink_env::call::FromAccountId::from_account_id(vault_address);
             let auth_instance = ink_env::call::FromAccountId::from_account_id(auth_address);
+            let org_instance = ink_env::call::FromAccountId::from_account_id(org_address);
             Self {
                 vault: vault_instance,
                 auth: auth_instance,
+                org: org_instance,
                 votes_length: 0,
                 votes: StorageHashMap::default(),
                 voters: StorageHashMap::default(),
@@ -168,51 +241,20 @@ mod vote_manager {
             choices: String,
         ) -> u64 {
             let caller = self.env().caller();
-            assert!(self
-                .auth
-                .has_permission(caller, String::from("vote"), String::from("new")));
-            let vote_id = self.votes_length.clone();
-            self.votes_length += 1;
-            let start_date: u64 = self.env().block_timestamp();
-            let vec: Vec<&str> = choices.split("|").collect();
-            let vote = Vote {
-                vote_id: vote_id,
-                executed: false,
+            assert!(self.has_vote_permission(caller, "new"));
+            self.create_vote(
                 title,
                 desc,
-                start_date: start_date,
                 vote_time,
-                need_trigger: false,
                 support_require_num,
                 min_require_num,
-                support_num: 0,
-                erc20_address: AccountId::default(),
-                to_address: AccountId::default(),
-                value: 0,
-                choice_index_lo: self.choices_num,
-                choice_index_ho: self.choices_num + vec.len() as u32,
-                status: 0,
-            };
-            let choices_len = vec.len() as u32;
-            if choices_len == 0 {
-                return 0;
-            }
-            self.choices_num += choices_len;
-            let mut index = 0;
-            for choice_content in vec.iter() {
-                self.choices.push(Choice {
-                    choice_id: index,
-                    content: String::from(*choice_content),
-                    yea: 0,
-                });
-                index += 1;
-            }
-            self.votes.insert(vote_id, vote);
-            self.env().emit_event(StartVote {
-                vote_id,
-                creator: self.env().caller(),
-            });
-            vote_id
+                choices,
+                false,
+                Asset::Native,
+                AccountId::default(),
+                0,
+                None,
+            )
         }
 
         #[ink(message)]
@@ -224,14 +266,128 @@ mod vote_manager {
             support_require_num: u64,
             min_require_num: u64,
             choices: String,
-            erc20_address: AccountId,
+            asset: Asset,
             to_address: AccountId,
-            value: u64,
+            value: Balance,
+        ) -> u64 {
+            let caller = self.env().caller();
+            assert!(self.has_vote_permission(caller, "new"));
+            self.create_vote(
+                title,
+                desc,
+                vote_time,
+                support_require_num,
+                min_require_num,
+                choices,
+                true,
+                asset,
+                to_address,
+                value,
+                None,
+            )
+        }
+
+        /// Create a vote that triggers `action` when its first choice reaches
+        /// `support_require_num`, so the first choice should be the affirmative one.
+        #[ink(message)]
+        pub fn new_vote_with_action(
+            &mut self,
+            title: String,
+            desc: String,
+            vote_time: u64,
+            support_require_num: u64,
+            min_require_num: u64,
+            choices: String,
+            action: VoteAction,
+        ) -> u64 {
+            let caller = self.env().caller();
+            assert!(self.has_vote_permission(caller, "new"));
+            self.create_vote(
+                title,
+                desc,
+                vote_time,
+                support_require_num,
+                min_require_num,
+                choices,
+                true,
+                Asset::Native,
+                AccountId::default(),
+                0,
+                Some(action),
+            )
+        }
+
+        /// Opened by the vault for a reimbursement request above its ceiling.
+        /// The selector is fixed because the vault calls it by selector.
+        #[ink(message, selector = "0x5a3c9e27")]
+        pub fn new_request_vote(
+            &mut self,
+            request_id: u64,
+            vote_time: u64,
+            support_require_num: u64,
+            min_require_num: u64,
+        ) -> u64 {
+            let caller = self.env().caller();
+            assert!(caller == ink_lang::ToAccountId::to_account_id(&self.vault));
+            self.create_vote(
+                format!("Reimbursement request #{}", request_id),
+                format!("Pay vault reimbursement request #{}", request_id),
+                vote_time,
+                support_require_num,
+                min_require_num,
+                String::from("yes|no"),
+                true,
+                Asset::Native,
+                AccountId::default(),
+                0,
+                Some(VoteAction::PayRequest { request_id }),
+            )
+        }
+
+        /// Opened by the org for every application while admission is by vote.
+        /// The selector is fixed because the org calls it by selector.
+        #[ink(message, selector = "0x713e0bd2")]
+        pub fn new_admission_vote(
+            &mut self,
+            member: AccountId,
+            application_id: u64,
+            vote_time: u64,
+            support_require_num: u64,
+            min_require_num: u64,
         ) -> u64 {
             let caller = self.env().caller();
-            assert!(self
-                .auth
-                .has_permission(caller, String::from("vote"), String::from("new")));
+            assert!(caller == ink_lang::ToAccountId::to_account_id(&self.org));
+            self.create_vote(
+                format!("Membership application #{}", application_id),
+                format!("Admit the applicant of membership application #{}", application_id),
+                vote_time,
+                support_require_num,
+                min_require_num,
+                String::from("yes|no"),
+                true,
+                Asset::Native,
+                AccountId::default(),
+                0,
+                Some(VoteAction::AdmitMember { member, application_id }),
+            )
+        }
+
+        fn create_vote(
+            &mut self,
+            title: String,
+            desc: String,
+            vote_time: u64,
+            support_require_num: u64,
+            min_require_num: u64,
+            choices: String,
+            need_trigger: bool,
+            asset: Asset,
+            to_address: AccountId,
+            value: Balance,
+            action: Option<VoteAction>,
+        ) -> u64 {
+            // while paused only the vote which lifts the pause can be started
+            assert!(!self.is_dao_paused() || action == Some(VoteAction::Unpause));
             let vote_id = self.votes_length.clone();
             self.votes_length += 1;
             let start_date: u64 = self.env().block_timestamp();
@@ -243,13 +399,14 @@ mod vote_manager {
                 desc,
                 start_date: start_date,
                 vote_time,
-                need_trigger: true,
+                need_trigger,
                 support_require_num,
                 min_require_num,
                 support_num: 0,
-                erc20_address,
+                asset,
                 to_address,
                 value,
+                action,
                 choice_index_lo: self.choices_num,
                 choice_index_ho: self.choices_num + vec.len() as u32,
                 status: 0,
@@ -282,6 +439,9 @@ mod vote_manager {
 
             let mut result = true;
             let current_time = self.env().block_timestamp();
+            let mut passed_action: Option<VoteAction> = None;
+
+            let paused = self.is_dao_paused();
 
             if let Some(vote) = self.votes.get_mut(&vote_id) {
                 if (current_time < vote.start_date + vote.vote_time || vote.executed)
@@ -290,10 +450,23 @@ mod vote_manager {
                     return true;
                 }
 
+                if paused && vote.action != Some(VoteAction::Unpause) {
+                    return false;
+                }
+
                 vote.executed = true;
 
+                // a failed request vote rejects the request in the vault
+                let request_id = match &vote.action {
+                    Some(VoteAction::PayRequest { request_id }) => Some(*request_id),
+                    _ => None,
+                };
+
                 if vote.support_num < vote.min_require_num || vote.support_num == 0 {
                     vote.status = 2;
+                    if let Some(request_id) = request_id {
+                        self.vault.decline_request(request_id);
+                    }
                     return false;
                 }
 
@@ -301,13 +474,20 @@ mod vote_manager {
                 let choices = &self.choices;
                 for choice in choices.iter() {
                     if index >= vote.choice_index_lo && index < vote.choice_index_ho {
+                        // an action only passes on its first, affirmative, choice
+                        if vote.action.is_some() && index > vote.choice_index_lo {
+                            break;
+                        }
                         if choice.yea >= vote.support_require_num {
                             vote.status = 1;
-                            if vote.need_trigger {
+                            if vote.action.is_some() {
+                                passed_action = vote.action.clone();
+                            } else if vote.need_trigger {
                                 result = self.vault.withdraw(
-                                    vote.erc20_address,
+                                    vote.asset,
                                     vote.to_address,
                                     vote.value,
+                                    Some(vote_id),
                                 );
                                 if !result {
                                     vote.status = 3;
@@ -319,22 +499,107 @@ mod vote_manager {
                     index += 1;
                 }
 
+                if vote.status == 0 {
+                    if let Some(request_id) = request_id {
+                        self.vault.decline_request(request_id);
+                    }
+                }
+
                 self.env().emit_event(ExecuteVote { vote_id });
             }
 
+            if let Some(action) = passed_action {
+                result = self.trigger_action(action);
+                if !result {
+                    self.votes.get_mut(&vote_id).unwrap().status = 3;
+                }
+            }
+
             result
         }
 
+        fn dao_token(&self) -> Option<Erc20> {
+            let dao_token = self.vault.get_dao_token()?;
+            Some(ink_env::call::FromAccountId::from_account_id(dao_token))
+        }
+
+        fn trigger_action(&mut self, action: VoteAction) -> bool {
+            match action {
+                VoteAction::CreateBudget {
+                    name,
+                    asset,
+                    spender,
+                    value,
+                } => self
+                    .vault
+                    .create_budget(name, asset, spender, value)
+                    .is_some(),
+                VoteAction::TopUpBudget { budget_id, value } => {
+                    self.vault.top_up_budget(budget_id, value)
+                }
+                VoteAction::ReclaimBudget { budget_id, value } => {
+                    self.vault.reclaim_budget(budget_id, value)
+                }
+                VoteAction::Unpause => self.auth.unpause(),
+                VoteAction::PayRequest { request_id } => self.vault.pay_request(request_id),
+                VoteAction::SetRequestPolicy {
+                    asset,
+                    ceiling,
+                    vote_time,
+                    support_require_num,
+                    min_require_num,
+                } => self.vault.set_request_policy(
+                    asset,
+                    ceiling,
+                    vote_time,
+                    support_require_num,
+                    min_require_num,
+                ),
+                VoteAction::Mint { to, value } => match self.dao_token() {
+                    None => false,
+                    Some(mut erc20_instance) => erc20_instance.mint(to, value).is_ok(),
+                },
+                VoteAction::AddMinter { minter } => match self.dao_token() {
+                    None => false,
+                    Some(mut erc20_instance) => erc20_instance.add_minter(minter).is_ok(),
+                },
+                VoteAction::RemoveMinter { minter } => match self.dao_token() {
+                    None => false,
+                    Some(mut erc20_instance) => erc20_instance.remove_minter(minter).is_ok(),
+                },
+                VoteAction::RemoveMember { member } => {
+                    let (is_member, is_moderator, _is_owner) = self.org.check_role_by_account(member);
+                    let mut removed = false;
+                    if is_moderator {
+                        removed = self.org.remove_dao_moderator(member);
+                    }
+                    if is_member {
+                        removed = self.org.remove_dao_member(member) || removed;
+                    }
+                    removed
+                }
+                VoteAction::CreateRole { name, actions } => self.org.create_role(name, actions),
+                VoteAction::AdmitMember { member, application_id } => {
+                    self.org.admit_member(member, application_id)
+                }
+            }
+        }
+
+        /// `voter` must be the caller, nobody can cast or hold back someone else's vote.
         #[ink(message)]
         pub fn vote(&mut self, vote_id: VoteId, support_choice: u32, voter: AccountId) -> bool {
-            if !self.vote_exists(vote_id) {
+            let caller = self.env().caller();
+            if voter != caller {
                 return false;
             }
-            let caller = self.env().caller();
-            if !self
-                .auth
-                .has_permission(caller, String::from("vote"), String::from("vote"))
-            {
+            if !self.has_vote_permission(caller, "vote") {
+                return false;
+            }
+            self.vote_private(vote_id, support_choice, voter)
+        }
+
+        fn vote_private(&mut self, vote_id: VoteId, support_choice: u32, voter: AccountId) -> bool {
+            if !self.vote_exists(vote_id) {
                 return false;
             }
             if let Some(vote) = self.votes.get_mut(&vote_id) {
@@ -360,7 +625,7 @@ mod vote_manager {
                 vote.support_num += 1;
                 self.env().emit_event(CastVote {
                     vote_id,
-                    voter: self.env().caller(),
+                    voter,
                     support_choice,
                 });
                 true
@@ -369,6 +634,41 @@ mod vote_manager {
             }
         }
 
+        /// Whether `voter` chose the first (affirmative) choice of a vote which pays out of the
+        /// treasury and hasn't been executed yet, open or queued after it closed. The selector is
+        /// fixed because the vault calls it by selector before a ragequit.
+        #[ink(message, selector = "0x2d8c4e11")]
+        pub fn has_pending_approval(&self, voter: AccountId) -> bool {
+            for (vote_id, vote) in &self.votes {
+                // a failed transfer (status 3) can be executed again
+                let settled = vote.executed && vote.status != 3;
+                if settled || !Self::moves_treasury(vote) {
+                    continue;
+                }
+                if let Some(choice_id) = self.voters.get(&(*vote_id, voter)) {
+                    if *choice_id == 0 {
+                        return true;
+                    }
+                }
+            }
+            false
+        }
+
+        /// `None` while the vote is open, afterwards whether its first choice passed. The selector
+        /// is fixed because the org calls it by selector to close failed admission votes.
+        #[ink(message, selector = "0x4e2f81a6")]
+        pub fn has_vote_passed(&self, vote_id: VoteId) -> Option<bool> {
+            let vote = self.votes.get(&vote_id)?;
+            if self.is_vote_open(vote) {
+                return None;
+            }
+            if vote.support_num < vote.min_require_num || vote.support_num == 0 {
+                return Some(false);
+            }
+            let first_choice = self.choices.get(vote.choice_index_lo)?;
+            Some(first_choice.yea >= vote.support_require_num)
+        }
+
         #[ink(message)]
         pub fn query_voter_vote_one(&self, vote_id: VoteId, voter: AccountId) -> bool {
             assert!(self.vote_exists(vote_id));
@@ -445,25 +745,23 @@ mod vote_manager {
                 index += 1;
             }
 
-            let erc20_instance: Erc20 =
-                ink_env::call::FromAccountId::from_account_id(vote.erc20_address);
-
             let (_erc20_symbol, _erc20_name, _erc20_balance) = if vote.need_trigger {
-                if vote.erc20_address == AccountId::from([0xee; 32]) {
-                    (
-                        String::from("gov"),
-                        String::from("subDAO"),
-                        self.vault.get_balance_of(vote.erc20_address),
-                        // erc20_instance
-                        //     .balance_of(ink_lang::ToAccountId::to_account_id(&self.vault)),
-                    )
-                } else {
-                    (
-                        erc20_instance.symbol(),
-                        erc20_instance.name(),
-                        erc20_instance
-                            .balance_of(ink_lang::ToAccountId::to_account_id(&self.vault)),
-                    )
+                match vote.asset {
+                    Asset::Native => (
+                        String::from(NATIVE_TOKEN_SYMBOL),
+                        String::from(NATIVE_TOKEN_NAME),
+                        self.vault.get_balance_of(vote.asset),
+                    ),
+                    Asset::Erc20(erc20_address) => {
+                        let erc20_instance: Erc20 =
+                            ink_env::call::FromAccountId::from_account_id(erc20_address);
+                        (
+                            erc20_instance.token_symbol().unwrap_or_default(),
+                            erc20_instance.token_name().unwrap_or_default(),
+                            erc20_instance
+                                .balance_of(ink_lang::ToAccountId::to_account_id(&self.vault)),
+                        )
+                    }
                 }
             } else {
                 (String::from(""), String::from(""), 0)
@@ -482,12 +780,13 @@ mod vote_manager {
                 min_require_num: vote.min_require_num,
                 support_num: vote.support_num,
                 choices: choices_content,
-                erc20_address: vote.erc20_address,
+                asset: vote.asset,
                 erc20_symbol: _erc20_symbol,
                 erc20_name: _erc20_name,
                 erc20_balance: _erc20_balance,
                 to_address: vote.to_address,
                 transfer_value: vote.value,
+                action: vote.action.clone(),
                 status: vote.status,
             };
             display_vote
@@ -505,6 +804,34 @@ mod vote_manager {
             result
         }
 
+        /// Plain transfer votes and actions which take funds out of the general balance.
+        fn moves_treasury(vote: &Vote) -> bool {
+            match &vote.action {
+                None => vote.need_trigger,
+                Some(VoteAction::CreateBudget { .. })
+                | Some(VoteAction::TopUpBudget { .. })
+                | Some(VoteAction::PayRequest { .. }) => true,
+                Some(_) => false,
+            }
+        }
+
+        /// No auth contract is linked while the address is the default one, votes can then be
+        /// neither started nor cast and nothing is paused.
+        fn auth_linked(&self) -> bool {
+            ink_lang::ToAccountId::to_account_id(&self.auth) != AccountId::default()
+        }
+
+        fn is_dao_paused(&self) -> bool {
+            self.auth_linked() && self.auth.is_paused()
+        }
+
+        /// Members whose membership lapsed can't use the permission, even before it is revoked.
+        fn has_vote_permission(&self, caller: AccountId, function_name: &str) -> bool {
+            self.auth_linked()
+                && self.auth.has_permission(caller, String::from("vote"), String::from(function_name))
+                && !self.org.is_lapsed_member(caller)
+        }
+
         fn is_vote_open(&self, vote: &Vote) -> bool {
             return self.env().block_timestamp() < vote.start_date + vote.vote_time;
         }
@@ -582,7 +909,7 @@ mod vote_manager {
                 .expect("Cannot get accounts");
             // after update votemanager need an vault_address to be initialized.
             // use alice address to replace here.
-            let vote_manager = VoteManager::new(accounts.alice, accounts.alice);
+            let vote_manager = VoteManager::new(accounts.alice, accounts.alice, accounts.alice);
 
             assert_eq!(vote_manager.votes_length, 0);
         }
@@ -591,7 +918,7 @@ mod vote_manager {
         fn full_test() {
             let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                 .expect("Cannot get accounts");
-            let mut vote_manager = VoteManager::new(accounts.alice, accounts.alice);
+            let mut vote_manager = VoteManager::new(accounts.alice, accounts.alice, accounts.alice);
 
             let r = vote_manager.new_vote(
                 "hello".to_string(),
@@ -630,7 +957,7 @@ mod vote_manager {
         fn vote_has_voted_test() {
             let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
                 .expect("Cannot get accounts");
-            let mut vote_manager = VoteManager::new(accounts.alice, accounts.alice);
+            let mut vote_manager = VoteManager::new(accounts.alice, accounts.alice, accounts.alice);
 
             let r = vote_manager.new_vote(
                 "hello".to_string(),
@@ -650,5 +977,46 @@ mod vote_manager {
             let has_voted = vote_manager.query_voter_vote_one(0, accounts.alice);
             assert_eq!(has_voted, true);
         }
+
+        #[ink::test]
+        fn pending_approval_test() {
+            let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts");
+            // no auth contract linked, so nothing calls into auth
+            let mut vote_manager = VoteManager::new(accounts.alice, AccountId::default(), accounts.alice);
+
+            // closed right away and queued, but not executed yet
+            let r = vote_manager.create_vote(
+                "hello".to_string(),
+                "hello world".to_string(),
+                0,
+                1,
+                0,
+                "yes|no".to_string(),
+                true,
+                Asset::Native,
+                accounts.bob,
+                10,
+                None,
+            );
+            assert_eq!(r, 0);
+
+            // the voter is bound to the caller
+            assert!(!vote_manager.vote(0, 0, accounts.bob));
+            assert!(vote_manager.vote_private(0, 0, accounts.alice));
+            assert!(vote_manager.vote_private(0, 1, accounts.bob));
+
+            assert_eq!(vote_manager.has_pending_approval(accounts.alice), true);
+            assert_eq!(vote_manager.has_pending_approval(accounts.bob), false);
+            assert_eq!(vote_manager.has_pending_approval(accounts.eve), false);
+
+            let vote = vote_manager.votes.get_mut(&0).unwrap();
+            vote.executed = true;
+            vote.status = 1;
+            assert_eq!(vote_manager.has_pending_approval(accounts.alice), false);
+            // a failed transfer is executed again
+            vote_manager.votes.get_mut(&0).unwrap().status = 3;
+            assert_eq!(vote_manager.has_pending_approval(accounts.alice), true);
+        }
     }
 }